@@ -253,6 +253,10 @@ pub fn execute_test_suite(
         kind: e.into(),
     })?;
 
+    // Units of a suite usually share the same pre-state code, so hash it only once.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    let mut poseidon_code_hashes = revm::primitives::PoseidonCodeHashCache::new();
+
     for (name, unit) in suite.0 {
         // Create database and insert cache
         let mut cache_state = revm::CacheState::new(false);
@@ -260,9 +264,10 @@ pub fn execute_test_suite(
             #[cfg(feature = "scroll")]
             let code_size = info.code.len();
             let keccak_code_hash = keccak256(&info.code);
-            #[cfg(feature = "scroll-poseidon-codehash")]
-            let poseidon_code_hash = revm::primitives::poseidon(&info.code);
             let bytecode = Bytecode::new_raw(info.code);
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            let poseidon_code_hash =
+                poseidon_code_hashes.get_or_compute(keccak_code_hash, &bytecode);
             let acc_info = revm::primitives::AccountInfo {
                 balance: info.balance,
                 #[cfg(feature = "scroll")]
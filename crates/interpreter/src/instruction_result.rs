@@ -113,6 +113,8 @@ impl From<HaltReason> for InstructionResult {
             HaltReason::EOFFunctionStackOverflow => Self::EOFFunctionStackOverflow,
             #[cfg(feature = "optimism")]
             HaltReason::FailedDeposit => Self::FatalExternalError,
+            #[cfg(feature = "scroll")]
+            HaltReason::PrecompileNotImplemented => Self::PrecompileError,
         }
     }
 }
@@ -115,7 +115,6 @@ pub fn extcodecopy<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter,
         .set_data(memory_offset, code_offset, len, &code);
 }
 
-#[cfg(not(feature = "scroll"))]
 pub fn blockhash<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter, host: &mut H) {
     gas!(interpreter, gas::BLOCKHASH);
     pop_top!(interpreter, number);
@@ -128,37 +127,6 @@ pub fn blockhash<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter, ho
     *number = U256::from_be_bytes(hash.0);
 }
 
-#[cfg(feature = "scroll")]
-pub fn blockhash<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter, host: &mut H) {
-    use revm_primitives::BLOCK_HASH_HISTORY;
-
-    gas!(interpreter, gas::BLOCKHASH);
-    pop_top!(interpreter, number);
-
-    let block_number = host.env().block.number;
-
-    match block_number.checked_sub(*number) {
-        Some(diff) if !diff.is_zero() => {
-            let diff = as_usize_saturated!(diff);
-            let block_number = as_u64_or_fail!(interpreter, number);
-
-            if SPEC::enabled(BERNOULLI) && diff <= BLOCK_HASH_HISTORY {
-                let mut hasher = crate::primitives::Keccak256::new();
-                hasher.update(host.env().cfg.chain_id.to_be_bytes());
-                hasher.update(block_number.to_be_bytes());
-                *number = U256::from_be_bytes(*hasher.finalize());
-                return;
-            }
-        }
-        _ => {
-            // If blockhash is requested for the current block, the hash should be 0, so we fall
-            // through.
-        }
-    }
-
-    *number = U256::ZERO;
-}
-
 pub fn sload<H: Host + ?Sized, SPEC: Spec>(interpreter: &mut Interpreter, host: &mut H) {
     pop_top!(interpreter, index);
     let Some((value, is_cold)) = host.sload(interpreter.contract.target_address, *index) else {
@@ -203,6 +203,115 @@ impl Bytecode {
     }
 }
 
+/// Default number of hashes kept by a [PoseidonCodeHashCache].
+#[cfg(feature = "scroll-poseidon-codehash")]
+pub const POSEIDON_CODE_HASH_CACHE_CAPACITY: usize = 4096;
+
+/// Poseidon code hashes memoized by their keccak code hash.
+///
+/// Poseidon hashing is expensive for large contracts, and the same bytecode is
+/// usually deployed or loaded many times, so it is computed only once per code hash.
+/// The cache is bounded, once full the oldest hash is evicted.
+#[cfg(feature = "scroll-poseidon-codehash")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoseidonCodeHashCache {
+    hashes: crate::HashMap<B256, B256>,
+    /// Keccak code hashes in insertion order, used for eviction.
+    order: std::collections::VecDeque<B256>,
+    capacity: usize,
+}
+
+#[cfg(feature = "scroll-poseidon-codehash")]
+impl Default for PoseidonCodeHashCache {
+    fn default() -> Self {
+        Self::with_capacity(POSEIDON_CODE_HASH_CACHE_CAPACITY)
+    }
+}
+
+#[cfg(feature = "scroll-poseidon-codehash")]
+impl PoseidonCodeHashCache {
+    /// Creates an empty cache with the default capacity.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty cache keeping at most `capacity` hashes.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            hashes: crate::HashMap::default(),
+            order: std::collections::VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Returns the cached poseidon hash of the code with the given keccak hash.
+    #[inline]
+    pub fn get(&self, code_hash: &B256) -> Option<B256> {
+        self.hashes.get(code_hash).copied()
+    }
+
+    /// Inserts an already known poseidon hash for the given keccak hash, evicting the oldest
+    /// hash if the cache is full.
+    pub fn insert(&mut self, code_hash: B256, poseidon_code_hash: B256) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.hashes.insert(code_hash, poseidon_code_hash).is_none() {
+            self.order.push_back(code_hash);
+            while self.order.len() > self.capacity {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.hashes.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    /// Returns the poseidon hash of the code, computing it only if the keccak
+    /// `code_hash` is not cached.
+    ///
+    /// Note: Assumes that `code_hash` is calculated from `code`.
+    #[inline]
+    pub fn get_or_compute(&mut self, code_hash: B256, code: &Bytecode) -> B256 {
+        if code_hash == KECCAK_EMPTY {
+            return crate::POSEIDON_EMPTY;
+        }
+        if let Some(poseidon_code_hash) = self.get(&code_hash) {
+            return poseidon_code_hash;
+        }
+        let poseidon_code_hash = code.poseidon_hash_slow();
+        self.insert(code_hash, poseidon_code_hash);
+        poseidon_code_hash
+    }
+
+    /// Returns the number of cached hashes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Returns `true` if no hash is cached.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Returns the maximum number of cached hashes.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Removes all cached hashes.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.hashes.clear();
+        self.order.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,4 +334,41 @@ mod tests {
             panic!("Original bytecode is not Eof");
         }
     }
+
+    #[test]
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    fn poseidon_code_hash_cache() {
+        use crate::{AccountInfo, Bytes, POSEIDON_EMPTY};
+
+        let mut cache = PoseidonCodeHashCache::with_capacity(2);
+        let bytecode = Bytecode::new_raw(Bytes::from_static(&[0x60, 0x00, 0x60, 0x00]));
+        let poseidon_code_hash = bytecode.poseidon_hash_slow();
+
+        assert_eq!(
+            cache.get_or_compute(KECCAK_EMPTY, &Bytecode::new()),
+            POSEIDON_EMPTY
+        );
+        assert!(cache.is_empty());
+
+        let code_hash = bytecode.hash_slow();
+        assert_eq!(
+            cache.get_or_compute(code_hash, &bytecode),
+            poseidon_code_hash
+        );
+        assert_eq!(cache.get(&code_hash), Some(poseidon_code_hash));
+
+        // the oldest hash is evicted once the cache is full.
+        cache.insert(B256::with_last_byte(1), B256::with_last_byte(1));
+        cache.insert(B256::with_last_byte(2), B256::with_last_byte(2));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&code_hash), None);
+
+        // accounts without a poseidon code hash are filled from the cache.
+        let mut info = AccountInfo::new(Default::default(), 0, code_hash, bytecode.clone());
+        assert_eq!(info.poseidon_code_hash, B256::ZERO);
+        assert_eq!(info.fill_poseidon_code_hash(&mut cache), poseidon_code_hash);
+        assert_eq!(cache.get(&code_hash), Some(poseidon_code_hash));
+        let mut info = AccountInfo::new(Default::default(), 0, code_hash, Bytecode::new());
+        assert_eq!(info.fill_poseidon_code_hash(&mut cache), poseidon_code_hash);
+    }
 }
@@ -330,6 +330,12 @@ pub struct CfgEnv {
     /// By default, it is set to `false`.
     #[cfg(feature = "optional_beneficiary_reward")]
     pub disable_beneficiary_reward: bool,
+    /// Checks that accounts loaded from the database have `code_size` and `poseidon_code_hash`
+    /// consistent with their code, see [`crate::AccountInfo::check_consistency`].
+    /// Inconsistent accounts are reported as [`crate::EVMError::Account`].
+    /// By default, it is set to `false`.
+    #[cfg(feature = "scroll")]
+    pub check_account_consistency: bool,
 }
 
 impl CfgEnv {
@@ -427,6 +433,8 @@ impl Default for CfgEnv {
             disable_base_fee: false,
             #[cfg(feature = "optional_beneficiary_reward")]
             disable_beneficiary_reward: false,
+            #[cfg(feature = "scroll")]
+            check_account_consistency: false,
         }
     }
 }
@@ -716,6 +724,15 @@ pub struct ScrollFields {
     /// The RLP-encoded bytes of the transaction. This is used
     /// to compute the L1 tx cost using the L1 block info.
     pub rlp_bytes: Option<Bytes>,
+    /// The index of the message in the L1 message queue.
+    ///
+    /// Only set for L1 message transactions.
+    pub queue_index: Option<u64>,
+    /// The hash of the L1 message transaction. This is folded into the
+    /// rolling L1 message queue hash committed by the batch.
+    ///
+    /// Only set for L1 message transactions.
+    pub message_hash: Option<B256>,
 }
 
 /// Transaction destination
@@ -52,6 +52,23 @@ impl ExecutionResult {
         matches!(self, Self::Halt { .. })
     }
 
+    /// Returns true if the transaction must be skipped and not included in the block.
+    ///
+    /// The state returned with such a result is empty, so committing it does not change the
+    /// database, not even the fee or the nonce of the caller.
+    ///
+    /// See [`HaltReason::PrecompileNotImplemented`].
+    #[cfg(feature = "scroll")]
+    pub fn must_skip(&self) -> bool {
+        matches!(
+            self,
+            Self::Halt {
+                reason: HaltReason::PrecompileNotImplemented,
+                ..
+            }
+        )
+    }
+
     /// Returns the output data of the execution.
     ///
     /// Returns `None` if the execution was halted.
@@ -150,6 +167,11 @@ pub enum EVMError<DBError> {
     Custom(String),
     /// Precompile error.
     Precompile(String),
+    /// Account loaded from the database is inconsistent.
+    ///
+    /// Only returned if [`crate::CfgEnv::check_account_consistency`] is enabled.
+    #[cfg(feature = "scroll")]
+    Account(InvalidAccount),
 }
 
 impl<DBError> EVMError<DBError> {
@@ -164,6 +186,8 @@ impl<DBError> EVMError<DBError> {
             Self::Database(e) => EVMError::Database(op(e)),
             Self::Precompile(e) => EVMError::Precompile(e),
             Self::Custom(e) => EVMError::Custom(e),
+            #[cfg(feature = "scroll")]
+            Self::Account(e) => EVMError::Account(e),
         }
     }
 }
@@ -175,6 +199,8 @@ impl<DBError: std::error::Error + 'static> std::error::Error for EVMError<DBErro
             Self::Transaction(e) => Some(e),
             Self::Header(e) => Some(e),
             Self::Database(e) => Some(e),
+            #[cfg(feature = "scroll")]
+            Self::Account(e) => Some(e),
             Self::Precompile(_) | Self::Custom(_) => None,
         }
     }
@@ -186,6 +212,8 @@ impl<DBError: fmt::Display> fmt::Display for EVMError<DBError> {
             Self::Transaction(e) => write!(f, "transaction validation error: {e}"),
             Self::Header(e) => write!(f, "header validation error: {e}"),
             Self::Database(e) => write!(f, "database error: {e}"),
+            #[cfg(feature = "scroll")]
+            Self::Account(e) => write!(f, "account validation error: {e}"),
             Self::Precompile(e) | Self::Custom(e) => f.write_str(e),
         }
     }
@@ -203,6 +231,13 @@ impl<DBError> From<InvalidHeader> for EVMError<DBError> {
     }
 }
 
+#[cfg(feature = "scroll")]
+impl<DBError> From<InvalidAccount> for EVMError<DBError> {
+    fn from(value: InvalidAccount) -> Self {
+        Self::Account(value)
+    }
+}
+
 /// Transaction validation error for Optimism.
 #[cfg(feature = "optimism")]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -421,6 +456,82 @@ impl fmt::Display for InvalidHeader {
     }
 }
 
+/// Account loaded from the database has inconsistent [`crate::AccountInfo`] fields.
+#[cfg(feature = "scroll")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InvalidAccount {
+    /// Address of the account.
+    pub address: Address,
+    /// Inconsistency found in the account info.
+    pub reason: AccountInconsistency,
+}
+
+#[cfg(all(feature = "scroll", feature = "std"))]
+impl std::error::Error for InvalidAccount {}
+
+#[cfg(feature = "scroll")]
+impl fmt::Display for InvalidAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "account {} is inconsistent: {}",
+            self.address, self.reason
+        )
+    }
+}
+
+/// Inconsistency between the code derived fields of an [`crate::AccountInfo`].
+///
+/// See [`crate::AccountInfo::check_consistency`].
+#[cfg(feature = "scroll")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AccountInconsistency {
+    /// Code hash is empty but `code_size` is not zero.
+    EmptyCodeWithCodeSize { code_size: usize },
+    /// Code hash is empty but `poseidon_code_hash` is not the empty poseidon hash.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    EmptyCodeWithPoseidonCodeHash { poseidon_code_hash: crate::B256 },
+    /// `code_size` is different from the length of the account code.
+    CodeSizeMismatch { code_size: usize, code_len: usize },
+    /// Code hash is not empty but `code_size` is zero and the code is not loaded.
+    CodeWithoutCodeSize,
+    /// Code hash is not empty but `poseidon_code_hash` is the empty poseidon hash.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    CodeWithEmptyPoseidonCodeHash,
+}
+
+#[cfg(feature = "scroll")]
+impl fmt::Display for AccountInconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyCodeWithCodeSize { code_size } => {
+                write!(f, "empty code hash with code size {code_size}")
+            }
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            Self::EmptyCodeWithPoseidonCodeHash { poseidon_code_hash } => {
+                write!(
+                    f,
+                    "empty code hash with poseidon code hash {poseidon_code_hash}"
+                )
+            }
+            Self::CodeSizeMismatch {
+                code_size,
+                code_len,
+            } => write!(
+                f,
+                "code size {code_size} does not match code length {code_len}"
+            ),
+            Self::CodeWithoutCodeSize => write!(f, "non-empty code hash with zero code size"),
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            Self::CodeWithEmptyPoseidonCodeHash => {
+                write!(f, "non-empty code hash with empty poseidon code hash")
+            }
+        }
+    }
+}
+
 /// Reason a transaction successfully completed.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -471,6 +582,13 @@ pub enum HaltReason {
     /* Optimism errors */
     #[cfg(feature = "optimism")]
     FailedDeposit,
+
+    /* Scroll errors */
+    /// Precompile input is not supported by the Scroll circuits.
+    ///
+    /// Transactions with this halt reason must be skipped instead of being included as failed.
+    #[cfg(feature = "scroll")]
+    PrecompileNotImplemented,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -223,6 +223,8 @@ pub struct AccountInfo {
     pub code_hash: B256,
     #[cfg(feature = "scroll-poseidon-codehash")]
     /// poseidon code hash, won't be calculated if code is not changed.
+    ///
+    /// `B256::ZERO` means that it was not calculated yet, see [AccountInfo::fill_poseidon_code_hash].
     pub poseidon_code_hash: B256,
     /// code: if None, `code_by_hash` will be used to fetch it if code needs to be loaded from
     /// inside of `revm`.
@@ -245,21 +247,10 @@ impl Default for AccountInfo {
 }
 
 impl PartialEq for AccountInfo {
-    #[allow(clippy::let_and_return)]
     fn eq(&self, other: &Self) -> bool {
-        let eq = self.balance == other.balance
+        self.balance == other.balance
             && self.nonce == other.nonce
-            && self.code_hash == other.code_hash;
-
-        #[cfg(all(debug_assertions, feature = "scroll"))]
-        if eq {
-            assert_eq!(self.code_size, other.code_size);
-            #[cfg(feature = "scroll-poseidon-codehash")]
-            if self.poseidon_code_hash != B256::ZERO && other.poseidon_code_hash != B256::ZERO {
-                assert_eq!(self.poseidon_code_hash, other.poseidon_code_hash);
-            }
-        }
-        eq
+            && self.code_hash == other.code_hash
     }
 }
 
@@ -299,15 +290,6 @@ impl AccountInfo {
     /// - nonce is zero
     pub fn is_empty(&self) -> bool {
         let code_empty = self.is_empty_code_hash() || self.code_hash == B256::ZERO;
-
-        #[cfg(all(feature = "scroll", debug_assertions))]
-        if code_empty {
-            assert_eq!(
-                self.code_size, 0,
-                "code size should be zero if code hash is empty"
-            );
-        }
-
         code_empty && self.balance == U256::ZERO && self.nonce == 0
     }
 
@@ -330,18 +312,54 @@ impl AccountInfo {
     /// Returns true if the code hash is the Keccak256 hash of the empty string `""`.
     #[inline]
     pub fn is_empty_code_hash(&self) -> bool {
-        #[cfg(all(debug_assertions, feature = "scroll-poseidon-codehash"))]
-        if self.code_hash == KECCAK_EMPTY {
-            assert_eq!(self.code_size, 0);
-            assert!(
-                self.poseidon_code_hash == crate::POSEIDON_EMPTY
-                    || self.poseidon_code_hash == B256::ZERO
-            );
-        }
-
         self.code_hash == KECCAK_EMPTY
     }
 
+    /// Checks that the code derived fields are consistent with the code hash and the code.
+    ///
+    /// - Empty code must have zero `code_size` and empty or not yet calculated `poseidon_code_hash`.
+    /// - Non-empty code must have a `code_size`, unless the code is loaded, and a
+    ///   `poseidon_code_hash` other than the empty one. A zero `poseidon_code_hash` is not
+    ///   calculated yet and accepted.
+    /// - If the code is present, `code_size` must be equal to its length.
+    #[cfg(feature = "scroll")]
+    pub fn check_consistency(&self) -> Result<(), crate::AccountInconsistency> {
+        use crate::AccountInconsistency;
+
+        if self.code_hash == KECCAK_EMPTY || self.code_hash == B256::ZERO {
+            if self.code_size != 0 {
+                return Err(AccountInconsistency::EmptyCodeWithCodeSize {
+                    code_size: self.code_size,
+                });
+            }
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            if self.poseidon_code_hash != crate::POSEIDON_EMPTY
+                && self.poseidon_code_hash != B256::ZERO
+            {
+                return Err(AccountInconsistency::EmptyCodeWithPoseidonCodeHash {
+                    poseidon_code_hash: self.poseidon_code_hash,
+                });
+            }
+        } else {
+            if self.code_size == 0 && self.code.is_none() {
+                return Err(AccountInconsistency::CodeWithoutCodeSize);
+            }
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            if self.poseidon_code_hash == crate::POSEIDON_EMPTY {
+                return Err(AccountInconsistency::CodeWithEmptyPoseidonCodeHash);
+            }
+        }
+        if let Some(code) = &self.code {
+            if code.len() != self.code_size {
+                return Err(AccountInconsistency::CodeSizeMismatch {
+                    code_size: self.code_size,
+                    code_len: code.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Take bytecode from account. Code will be set to None.
     pub fn take_bytecode(&mut self) -> Option<Bytecode> {
         self.code.take()
@@ -381,6 +399,24 @@ impl AccountInfo {
             }
         }
     }
+
+    /// Calculates the poseidon code hash if it was not calculated yet and returns it.
+    ///
+    /// The hash is looked up in `cache` by the keccak code hash first. If the code is not
+    /// present the hash can't be calculated and is left unchanged.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    pub fn fill_poseidon_code_hash(&mut self, cache: &mut crate::PoseidonCodeHashCache) -> B256 {
+        if self.poseidon_code_hash.is_zero() {
+            if self.is_empty_code_hash() {
+                self.poseidon_code_hash = crate::POSEIDON_EMPTY;
+            } else if let Some(code) = &self.code {
+                self.poseidon_code_hash = cache.get_or_compute(self.code_hash, code);
+            } else if let Some(poseidon_code_hash) = cache.get(&self.code_hash) {
+                self.poseidon_code_hash = poseidon_code_hash;
+            }
+        }
+        self.poseidon_code_hash
+    }
 }
 
 #[cfg(test)]
@@ -465,4 +501,35 @@ mod tests {
         // When marking cold account as warm, it should return true
         assert!(account.mark_warm());
     }
+
+    #[test]
+    #[cfg(feature = "scroll")]
+    fn account_info_code_consistency() {
+        use crate::{AccountInconsistency, AccountInfo, Bytecode, Bytes};
+
+        let info = AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[0x00])));
+        assert_eq!(info.check_consistency(), Ok(()));
+        assert_eq!(info.clone().without_code().check_consistency(), Ok(()));
+
+        let mut missing_size = info.clone().without_code();
+        missing_size.code_size = 0;
+        assert_eq!(
+            missing_size.check_consistency(),
+            Err(AccountInconsistency::CodeWithoutCodeSize)
+        );
+
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        {
+            // poseidon code hash is calculated lazily, see `AccountInfo::new`.
+            let mut info = info.clone();
+            info.poseidon_code_hash = crate::B256::ZERO;
+            assert_eq!(info.check_consistency(), Ok(()));
+
+            info.poseidon_code_hash = crate::POSEIDON_EMPTY;
+            assert_eq!(
+                info.check_consistency(),
+                Err(AccountInconsistency::CodeWithEmptyPoseidonCodeHash)
+            );
+        }
+    }
 }
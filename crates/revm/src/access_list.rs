@@ -0,0 +1,123 @@
+//! Access list generation, same as the `eth_createAccessList` RPC method.
+
+use crate::{
+    inspector_handle_register,
+    inspectors::AccessListInspector,
+    primitives::{AccessListItem, EVMError, EnvWithHandlerCfg, ExecutionResult, ResultAndState},
+    Database, Evm,
+};
+use std::vec::Vec;
+
+/// Result of [Evm::create_access_list].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessListResult {
+    /// Generated access list.
+    pub access_list: Vec<AccessListItem>,
+    /// Gas used by the transaction with the generated access list.
+    pub gas_used: u64,
+    /// Gas used by the transaction without an access list.
+    pub gas_used_without_access_list: u64,
+    /// Result of the transaction with the generated access list.
+    pub result: ExecutionResult,
+}
+
+impl<EXT, DB: Database> Evm<'_, EXT, DB> {
+    /// Generates the access list of the current transaction.
+    ///
+    /// The transaction is executed with an [AccessListInspector] and re-executed with the
+    /// generated access list until the list does not change anymore, as accessed keys can
+    /// depend on the gas left. The access list of the transaction is used as a starting point.
+    ///
+    /// Transactions are executed on a new [Evm] with the same environment and handler
+    /// configuration, custom handler registers of this [Evm] are not applied. Nothing is
+    /// committed to the database.
+    pub fn create_access_list(&mut self) -> Result<AccessListResult, EVMError<DB::Error>> {
+        let mut env = EnvWithHandlerCfg::new(self.context.evm.env.clone(), self.handler.cfg);
+        let initial_access_list = core::mem::take(&mut env.tx.access_list);
+
+        // first run is without an access list.
+        let (result, mut access_list) =
+            self.transact_with_access_list(&env, &initial_access_list)?;
+        let gas_used_without_access_list = result.gas_used();
+        loop {
+            env.tx.access_list.clone_from(&access_list);
+            let (result, next) = self.transact_with_access_list(&env, &access_list)?;
+            if next == access_list {
+                return Ok(AccessListResult {
+                    access_list,
+                    gas_used: result.gas_used(),
+                    gas_used_without_access_list,
+                    result,
+                });
+            }
+            access_list = next;
+        }
+    }
+
+    /// Executes the transaction of `env`, returns the result and the access list generated
+    /// starting from `access_list`.
+    fn transact_with_access_list(
+        &mut self,
+        env: &EnvWithHandlerCfg,
+        access_list: &[AccessListItem],
+    ) -> Result<(ExecutionResult, Vec<AccessListItem>), EVMError<DB::Error>> {
+        let mut evm = Evm::builder()
+            .with_db(&mut self.context.evm.inner.db)
+            .with_external_context(AccessListInspector::new(access_list))
+            .with_env_with_handler_cfg(env.clone())
+            .append_handler_register(inspector_handle_register)
+            .build();
+        let ResultAndState { result, .. } = evm.transact()?;
+        Ok((result, evm.context.external.access_list()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        primitives::{address, AccessListItem, AccountInfo, Bytecode, Bytes, TxKind, B256, U256},
+        test_utils::{test_evm_builder, TEST_CONTRACT},
+    };
+
+    #[test]
+    fn test_create_access_list() {
+        let other = address!("3000000000000000000000000000000000000000");
+
+        let mut db = CacheDB::new(EmptyDB::default());
+        // SLOAD(1), BALANCE(other), BALANCE(caller), STOP
+        let mut code = vec![0x60, 0x01, 0x54, 0x50, 0x73];
+        code.extend_from_slice(other.as_slice());
+        code.extend_from_slice(&[0x31, 0x50, 0x33, 0x31, 0x50, 0x00]);
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from(code))),
+        );
+
+        let mut evm = test_evm_builder(db, TxKind::Call(TEST_CONTRACT)).build();
+        let result = evm.create_access_list().unwrap();
+
+        assert_eq!(
+            result.access_list,
+            vec![
+                AccessListItem {
+                    address: TEST_CONTRACT,
+                    storage_keys: vec![B256::from(U256::from(1))],
+                },
+                AccessListItem {
+                    address: other,
+                    storage_keys: vec![],
+                },
+            ]
+        );
+        assert!(result.result.is_success());
+        // list costs 2400 per address and 1900 per key, cold SLOAD and BALANCE get 2000 and
+        // 2500 cheaper. Recipient is in the list because of its storage key.
+        assert_eq!(
+            result.gas_used,
+            result.gas_used_without_access_list + 2 * 2400 + 1900 - 2000 - 2500
+        );
+        // transaction of the evm is not modified.
+        assert!(evm.tx().access_list.is_empty());
+    }
+}
@@ -0,0 +1,347 @@
+//! Providers of the block hashes returned by the `BLOCKHASH` opcode.
+
+use crate::{
+    db::Database,
+    primitives::{
+        Env, SpecId, B256, BLOCKHASH_SERVE_WINDOW, BLOCKHASH_STORAGE_ADDRESS, BLOCK_HASH_HISTORY,
+        U256,
+    },
+};
+use core::fmt;
+use dyn_clone::DynClone;
+use std::boxed::Box;
+
+/// Source of the block hashes returned by the `BLOCKHASH` opcode.
+///
+/// Each provider serves the hashes of a window of blocks before the current block of the
+/// environment, other block numbers return zero.
+pub trait BlockHashProvider<DB: Database>: DynClone + Send + Sync {
+    /// Returns the hash of the block `number`.
+    fn block_hash(
+        &mut self,
+        spec_id: SpecId,
+        env: &Env,
+        db: &mut DB,
+        number: u64,
+    ) -> Result<B256, DB::Error>;
+}
+
+dyn_clone::clone_trait_object!(<DB> BlockHashProvider<DB>);
+
+impl<DB: Database> fmt::Debug for dyn BlockHashProvider<DB> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BlockHashProvider")
+    }
+}
+
+/// Box over a block hash provider, as stored in the context.
+pub type BlockHashProviderBox<DB> = Box<dyn BlockHashProvider<DB>>;
+
+/// Returns `true` if `number` is one of the `window` blocks before the current block.
+#[inline]
+fn in_window(env: &Env, number: u64, window: usize) -> bool {
+    let current = u64::try_from(env.block.number).unwrap_or(u64::MAX);
+    current
+        .checked_sub(number)
+        .is_some_and(|diff| diff != 0 && diff <= window as u64)
+}
+
+/// Bounded ring buffer of the hashes of the last [BLOCK_HASH_HISTORY] blocks.
+///
+/// Hashes that are not in the buffer are loaded with [Database::block_hash], a hash only
+/// stays in the buffer until a block [BLOCK_HASH_HISTORY] blocks later is inserted. Meant for
+/// databases that do not cache block hashes themselves, unlike [crate::db::State] and
+/// [crate::db::CacheDB].
+///
+/// Hashes are indexed by block number only, so after a reorg the buffer has to be cleared or
+/// hashes of the abandoned blocks are returned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockHashRing {
+    hashes: Box<[Option<(u64, B256)>; BLOCK_HASH_HISTORY]>,
+}
+
+impl Default for BlockHashRing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockHashRing {
+    /// Creates an empty buffer.
+    pub fn new() -> Self {
+        Self {
+            hashes: Box::new([None; BLOCK_HASH_HISTORY]),
+        }
+    }
+
+    /// Inserts the hash of the block, replacing the block [BLOCK_HASH_HISTORY] blocks apart.
+    pub fn insert(&mut self, number: u64, hash: B256) {
+        self.hashes[Self::slot(number)] = Some((number, hash));
+    }
+
+    /// Returns the hash of the block if it is in the buffer.
+    pub fn get(&self, number: u64) -> Option<B256> {
+        match self.hashes[Self::slot(number)] {
+            Some((slot_number, hash)) if slot_number == number => Some(hash),
+            _ => None,
+        }
+    }
+
+    /// Removes all hashes.
+    pub fn clear(&mut self) {
+        self.hashes.fill(None);
+    }
+
+    #[inline]
+    fn slot(number: u64) -> usize {
+        (number % BLOCK_HASH_HISTORY as u64) as usize
+    }
+}
+
+impl<DB: Database> BlockHashProvider<DB> for BlockHashRing {
+    fn block_hash(
+        &mut self,
+        _spec_id: SpecId,
+        env: &Env,
+        db: &mut DB,
+        number: u64,
+    ) -> Result<B256, DB::Error> {
+        if !in_window(env, number, BLOCK_HASH_HISTORY) {
+            return Ok(B256::ZERO);
+        }
+        if let Some(hash) = self.get(number) {
+            return Ok(hash);
+        }
+        let hash = db.block_hash(number)?;
+        self.insert(number, hash);
+        Ok(hash)
+    }
+}
+
+/// [EIP-2935](https://eips.ethereum.org/EIPS/eip-2935) reader of the hashes stored in the
+/// history storage contract.
+///
+/// The hash of the block is read from the slot `number % BLOCKHASH_SERVE_WINDOW` of
+/// [BLOCKHASH_STORAGE_ADDRESS], which is written by the system call at the start of each block.
+/// The `BLOCKHASH` opcode keeps using the last [BLOCK_HASH_HISTORY] hashes of the database
+/// after Prague, this provider is only used if it is set explicitly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Eip2935BlockHashes;
+
+impl<DB: Database> BlockHashProvider<DB> for Eip2935BlockHashes {
+    fn block_hash(
+        &mut self,
+        _spec_id: SpecId,
+        env: &Env,
+        db: &mut DB,
+        number: u64,
+    ) -> Result<B256, DB::Error> {
+        if !in_window(env, number, BLOCKHASH_SERVE_WINDOW) {
+            return Ok(B256::ZERO);
+        }
+        let index = U256::from(number % BLOCKHASH_SERVE_WINDOW as u64);
+        db.storage(BLOCKHASH_STORAGE_ADDRESS, index)
+            .map(|value| value.to_be_bytes().into())
+    }
+}
+
+/// Scroll block hashes, derived from the chain id and the block number.
+///
+/// Since Bernoulli the hash of each of the last [BLOCK_HASH_HISTORY] blocks is
+/// `keccak256(chain_id ++ number)`, with both encoded as big-endian `u64`. Before Bernoulli all
+/// hashes are zero. The database is never queried.
+#[cfg(feature = "scroll")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScrollBlockHashes;
+
+#[cfg(feature = "scroll")]
+impl ScrollBlockHashes {
+    /// Returns the synthetic hash of the block.
+    pub fn hash(chain_id: u64, number: u64) -> B256 {
+        let mut hasher = crate::primitives::Keccak256::new();
+        hasher.update(chain_id.to_be_bytes());
+        hasher.update(number.to_be_bytes());
+        hasher.finalize()
+    }
+}
+
+#[cfg(feature = "scroll")]
+impl<DB: Database> BlockHashProvider<DB> for ScrollBlockHashes {
+    fn block_hash(
+        &mut self,
+        spec_id: SpecId,
+        env: &Env,
+        _db: &mut DB,
+        number: u64,
+    ) -> Result<B256, DB::Error> {
+        if !spec_id.is_enabled_in(SpecId::BERNOULLI) || !in_window(env, number, BLOCK_HASH_HISTORY)
+        {
+            return Ok(B256::ZERO);
+        }
+        Ok(Self::hash(env.cfg.chain_id, number))
+    }
+}
+
+/// Default block hash provider of the context.
+///
+/// The hashes of the last [BLOCK_HASH_HISTORY] blocks are loaded with [Database::block_hash],
+/// without caching. With the `scroll` feature the hashes are the ones of `ScrollBlockHashes`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpecBlockHashProvider;
+
+#[cfg(not(feature = "scroll"))]
+impl<DB: Database> BlockHashProvider<DB> for SpecBlockHashProvider {
+    fn block_hash(
+        &mut self,
+        _spec_id: SpecId,
+        env: &Env,
+        db: &mut DB,
+        number: u64,
+    ) -> Result<B256, DB::Error> {
+        if !in_window(env, number, BLOCK_HASH_HISTORY) {
+            return Ok(B256::ZERO);
+        }
+        db.block_hash(number)
+    }
+}
+
+#[cfg(feature = "scroll")]
+impl<DB: Database> BlockHashProvider<DB> for SpecBlockHashProvider {
+    fn block_hash(
+        &mut self,
+        spec_id: SpecId,
+        env: &Env,
+        db: &mut DB,
+        number: u64,
+    ) -> Result<B256, DB::Error> {
+        ScrollBlockHashes.block_hash(spec_id, env, db, number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        primitives::{AccountInfo, Bytecode, Bytes, TxKind},
+        test_utils::{test_evm_builder, TEST_CONTRACT},
+    };
+
+    fn env(number: u64) -> Env {
+        let mut env = Env::default();
+        env.block.number = U256::from(number);
+        env
+    }
+
+    #[test]
+    fn test_ring_window() {
+        let mut db = CacheDB::new(EmptyDB::default());
+        let mut ring = BlockHashRing::new();
+        let env = env(1000);
+
+        for number in [1000, 1001, 743] {
+            assert_eq!(
+                ring.block_hash(SpecId::CANCUN, &env, &mut db, number),
+                Ok(B256::ZERO)
+            );
+        }
+        let hash = ring.block_hash(SpecId::CANCUN, &env, &mut db, 744).unwrap();
+        assert_eq!(hash, db.block_hash(744).unwrap());
+        assert_eq!(ring.get(744), Some(hash));
+
+        // block 1000 takes the slot of block 744.
+        ring.insert(1000, B256::with_last_byte(1));
+        assert_eq!(ring.get(744), None);
+        assert_eq!(ring.get(1000), Some(B256::with_last_byte(1)));
+    }
+
+    #[test]
+    fn test_eip2935() {
+        let mut db = CacheDB::new(EmptyDB::default());
+        let index = U256::from(10_000 % BLOCKHASH_SERVE_WINDOW as u64);
+        db.insert_account_storage(BLOCKHASH_STORAGE_ADDRESS, index, U256::from(7))
+            .unwrap();
+
+        let env = env(10_000 + BLOCKHASH_SERVE_WINDOW as u64);
+        let hash = Eip2935BlockHashes.block_hash(SpecId::PRAGUE, &env, &mut db, 10_000);
+        assert_eq!(hash, Ok(B256::with_last_byte(7)));
+        let hash = Eip2935BlockHashes.block_hash(SpecId::PRAGUE, &env, &mut db, 9_999);
+        assert_eq!(hash, Ok(B256::ZERO));
+    }
+
+    #[cfg(not(feature = "scroll"))]
+    #[test]
+    fn test_spec_provider() {
+        let mut db = CacheDB::new(EmptyDB::default());
+        let index = U256::from(999 % BLOCKHASH_SERVE_WINDOW as u64);
+        db.insert_account_storage(BLOCKHASH_STORAGE_ADDRESS, index, U256::from(7))
+            .unwrap();
+
+        // the history contract is not read after Prague.
+        let env = env(1000);
+        let hash = SpecBlockHashProvider.block_hash(SpecId::PRAGUE, &env, &mut db, 999);
+        assert_eq!(hash, db.block_hash(999));
+        let hash = SpecBlockHashProvider.block_hash(SpecId::PRAGUE, &env, &mut db, 743);
+        assert_eq!(hash, Ok(B256::ZERO));
+    }
+
+    #[cfg(feature = "scroll")]
+    #[test]
+    fn test_scroll() {
+        let mut db = EmptyDB::default();
+        let mut env = env(100);
+        env.cfg.chain_id = 534352;
+
+        let hash = ScrollBlockHashes.block_hash(SpecId::BERNOULLI, &env, &mut db, 99);
+        assert_eq!(hash, Ok(ScrollBlockHashes::hash(534352, 99)));
+        assert_ne!(hash, Ok(B256::ZERO));
+        let hash = ScrollBlockHashes.block_hash(SpecId::PRE_BERNOULLI, &env, &mut db, 99);
+        assert_eq!(hash, Ok(B256::ZERO));
+        let hash = ScrollBlockHashes.block_hash(SpecId::BERNOULLI, &env, &mut db, 100);
+        assert_eq!(hash, Ok(B256::ZERO));
+    }
+
+    /// Returns the same hash for every block.
+    #[derive(Clone)]
+    struct FixedBlockHashes(B256);
+
+    impl<DB: Database> BlockHashProvider<DB> for FixedBlockHashes {
+        fn block_hash(
+            &mut self,
+            _spec_id: SpecId,
+            _env: &Env,
+            _db: &mut DB,
+            _number: u64,
+        ) -> Result<B256, DB::Error> {
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn test_custom_provider() {
+        let mut db = CacheDB::new(EmptyDB::default());
+        // SSTORE(0, BLOCKHASH(5))
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                0x60, 0x05, 0x40, 0x60, 0x00, 0x55, 0x00,
+            ]))),
+        );
+        let hash = B256::with_last_byte(42);
+        let mut evm = test_evm_builder(db, TxKind::Call(TEST_CONTRACT))
+            .modify_block_env(|block| block.number = U256::from(10))
+            .with_block_hash_provider(FixedBlockHashes(hash))
+            .build();
+        let state = evm.transact().unwrap().state;
+        let slot = state[&TEST_CONTRACT].storage[&U256::ZERO].present_value();
+        assert_eq!(slot, U256::from_be_bytes(hash.0));
+    }
+
+    #[test]
+    #[should_panic = "the block hash provider must be set after the database"]
+    fn test_custom_provider_db_change() {
+        test_evm_builder(EmptyDB::default(), TxKind::Call(TEST_CONTRACT))
+            .with_block_hash_provider(FixedBlockHashes(B256::ZERO))
+            .with_db(CacheDB::new(EmptyDB::default()));
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     primitives::{
         BlockEnv, CfgEnv, CfgEnvWithHandlerCfg, Env, EnvWithHandlerCfg, HandlerCfg, SpecId, TxEnv,
     },
-    Context, ContextWithHandlerCfg, Evm, Handler,
+    BlockHashProvider, Context, ContextWithHandlerCfg, Evm, Handler,
 };
 use core::marker::PhantomData;
 use std::boxed::Box;
@@ -431,6 +431,21 @@ impl<'a, BuilderStage, EXT, DB: Database> EvmBuilder<'a, BuilderStage, EXT, DB>
         self
     }
 
+    /// Sets the provider of the block hashes returned by the `BLOCKHASH` opcode, instead of the
+    /// [`SpecBlockHashProvider`].
+    ///
+    /// The provider is bound to the database type, so it has to be set after the database.
+    /// Changing the database afterwards panics.
+    ///
+    /// [`SpecBlockHashProvider`]: crate::SpecBlockHashProvider
+    pub fn with_block_hash_provider(
+        mut self,
+        provider: impl BlockHashProvider<DB> + 'static,
+    ) -> Self {
+        self.context.evm.block_hash_provider = Some(Box::new(provider));
+        self
+    }
+
     /// Allows modification of Evm's Config Environment.
     pub fn modify_cfg_env(mut self, f: impl FnOnce(&mut CfgEnv)) -> Self {
         f(&mut self.context.evm.env.cfg);
@@ -2,19 +2,17 @@ mod context_precompiles;
 pub(crate) mod evm_context;
 mod inner_evm_context;
 
+use crate::{
+    db::{Database, EmptyDB},
+    interpreter::{Host, LoadAccountResult, SStoreResult, SelfDestructResult},
+    primitives::{Address, Bytes, Env, HandlerCfg, Log, B256, U256},
+};
 pub use context_precompiles::{
     ContextPrecompile, ContextPrecompiles, ContextStatefulPrecompile, ContextStatefulPrecompileArc,
     ContextStatefulPrecompileBox, ContextStatefulPrecompileMut,
 };
 pub use evm_context::EvmContext;
 pub use inner_evm_context::InnerEvmContext;
-use revm_interpreter::as_usize_saturated;
-
-use crate::{
-    db::{Database, EmptyDB},
-    interpreter::{Host, LoadAccountResult, SStoreResult, SelfDestructResult},
-    primitives::{Address, Bytes, Env, HandlerCfg, Log, B256, BLOCK_HASH_HISTORY, U256},
-};
 use std::boxed::Box;
 
 /// Main Context structure that contains both EvmContext and External context.
@@ -109,27 +107,10 @@ impl<EXT, DB: Database> Host for Context<EXT, DB> {
     }
 
     fn block_hash(&mut self, number: u64) -> Option<B256> {
-        let block_number = as_usize_saturated!(self.env().block.number);
-        let requested_number = usize::try_from(number).unwrap_or(usize::MAX);
-
-        let Some(diff) = block_number.checked_sub(requested_number) else {
-            return Some(B256::ZERO);
-        };
-
-        // blockhash should push zero if number is same as current block number.
-        if diff == 0 {
-            return Some(B256::ZERO);
-        }
-
-        if diff <= BLOCK_HASH_HISTORY {
-            return self
-                .evm
-                .block_hash(number)
-                .map_err(|e| self.evm.error = Err(e))
-                .ok();
-        }
-
-        Some(B256::ZERO)
+        self.evm
+            .block_hash(number)
+            .map_err(|e| self.evm.error = Err(e))
+            .ok()
     }
 
     fn load_account(&mut self, address: Address) -> Option<LoadAccountResult> {
@@ -138,6 +138,10 @@ impl<DB: Database> EvmContext<DB> {
                 }
             }
             Err(PrecompileErrors::Error(e)) => {
+                #[cfg(feature = "scroll")]
+                if e == crate::primitives::PrecompileError::NotImplemented {
+                    self.inner.precompile_not_implemented = true;
+                }
                 result.result = if e.is_oog() {
                     InstructionResult::PrecompileOOG
                 } else {
@@ -515,8 +519,11 @@ pub(crate) mod test_utils {
                 db,
                 error: Ok(()),
                 valid_authorizations: Vec::new(),
+                block_hash_provider: None,
                 #[cfg(any(feature = "optimism", feature = "scroll"))]
                 l1_block_info: None,
+                #[cfg(feature = "scroll")]
+                precompile_not_implemented: false,
             },
             precompiles: ContextPrecompiles::default(),
         }
@@ -531,8 +538,11 @@ pub(crate) mod test_utils {
                 db,
                 error: Ok(()),
                 valid_authorizations: Default::default(),
+                block_hash_provider: None,
                 #[cfg(any(feature = "optimism", feature = "scroll"))]
                 l1_block_info: None,
+                #[cfg(feature = "scroll")]
+                precompile_not_implemented: false,
             },
             precompiles: ContextPrecompiles::default(),
         }
@@ -637,4 +647,77 @@ mod tests {
         };
         assert_eq!(call_frame.return_memory_range, 0..0,);
     }
+
+    #[cfg(feature = "scroll")]
+    #[test]
+    fn test_load_inconsistent_account() {
+        use crate::{
+            db::DbAccount,
+            primitives::{AccountInconsistency, InvalidAccount},
+        };
+
+        let env = Env::default();
+        let mut cdb = CacheDB::new(EmptyDB::default());
+        let address = address!("dead10000000000000000000000000000001dead");
+        cdb.accounts.insert(
+            address,
+            DbAccount::from(crate::primitives::AccountInfo {
+                code_size: 1,
+                ..Default::default()
+            }),
+        );
+        let mut evm_context = create_cache_db_evm_context(Box::new(env), cdb);
+
+        // not checked by default.
+        assert!(evm_context.load_account(address).is_ok());
+
+        evm_context.journaled_state.state.clear();
+        evm_context.journaled_state.check_account_consistency = true;
+        assert_eq!(
+            evm_context.load_account(address).map(|_| ()),
+            Err(EVMError::Account(InvalidAccount {
+                address,
+                reason: AccountInconsistency::EmptyCodeWithCodeSize { code_size: 1 },
+            }))
+        );
+    }
+
+    #[cfg(feature = "scroll")]
+    #[test]
+    fn test_transact_inconsistent_caller() {
+        use crate::{
+            db::DbAccount,
+            primitives::{AccountInconsistency, InvalidAccount},
+            Evm,
+        };
+
+        let caller = address!("1000000000000000000000000000000000000000");
+        let mut cdb = CacheDB::new(EmptyDB::default());
+        cdb.accounts.insert(
+            caller,
+            DbAccount::from(crate::primitives::AccountInfo {
+                code_size: 1,
+                ..Default::default()
+            }),
+        );
+        let mut evm = Evm::builder()
+            .with_db(cdb)
+            .modify_env(|env| {
+                env.cfg.check_account_consistency = true;
+                env.tx.caller = caller;
+            })
+            .build();
+
+        // caller is checked before the transaction is validated against it.
+        assert_eq!(
+            evm.transact().map(|_| ()),
+            Err(EVMError::Account(InvalidAccount {
+                address: caller,
+                reason: AccountInconsistency::EmptyCodeWithCodeSize { code_size: 1 },
+            }))
+        );
+
+        evm.context.evm.env.cfg.check_account_consistency = false;
+        assert!(evm.transact().is_ok());
+    }
 }
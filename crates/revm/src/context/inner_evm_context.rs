@@ -1,4 +1,5 @@
 use crate::{
+    block_hash::{BlockHashProvider, BlockHashProviderBox, SpecBlockHashProvider},
     db::Database,
     interpreter::{
         analysis::to_analysed, gas, return_ok, InstructionResult, InterpreterResult,
@@ -29,12 +30,20 @@ pub struct InnerEvmContext<DB: Database> {
     pub error: Result<(), EVMError<DB::Error>>,
     /// EIP-7702 Authorization list of accounts that needs to be cleared.
     pub valid_authorizations: Vec<Address>,
+    /// Provider of the hashes returned by the `BLOCKHASH` opcode, [SpecBlockHashProvider] if
+    /// `None`.
+    pub block_hash_provider: Option<BlockHashProviderBox<DB>>,
     /// Used as temporary value holder to store L1 block info.
     #[cfg(feature = "optimism")]
     pub l1_block_info: Option<crate::optimism::L1BlockInfo>,
     /// Used as temporary value holder to store L1 block info.
     #[cfg(feature = "scroll")]
     pub l1_block_info: Option<crate::scroll::L1BlockInfo>,
+    /// Set if a precompile returned [`PrecompileError::NotImplemented`] during the transaction.
+    ///
+    /// [`PrecompileError::NotImplemented`]: crate::primitives::PrecompileError::NotImplemented
+    #[cfg(feature = "scroll")]
+    pub precompile_not_implemented: bool,
 }
 
 impl<DB: Database + Clone> Clone for InnerEvmContext<DB>
@@ -48,8 +57,11 @@ where
             db: self.db.clone(),
             error: self.error.clone(),
             valid_authorizations: self.valid_authorizations.clone(),
+            block_hash_provider: self.block_hash_provider.clone(),
             #[cfg(any(feature = "optimism", feature = "scroll"))]
             l1_block_info: self.l1_block_info.clone(),
+            #[cfg(feature = "scroll")]
+            precompile_not_implemented: self.precompile_not_implemented,
         }
     }
 }
@@ -62,8 +74,11 @@ impl<DB: Database> InnerEvmContext<DB> {
             db,
             error: Ok(()),
             valid_authorizations: Default::default(),
+            block_hash_provider: None,
             #[cfg(any(feature = "optimism", feature = "scroll"))]
             l1_block_info: None,
+            #[cfg(feature = "scroll")]
+            precompile_not_implemented: false,
         }
     }
 
@@ -76,24 +91,38 @@ impl<DB: Database> InnerEvmContext<DB> {
             db,
             error: Ok(()),
             valid_authorizations: Default::default(),
+            block_hash_provider: None,
             #[cfg(any(feature = "optimism", feature = "scroll"))]
             l1_block_info: None,
+            #[cfg(feature = "scroll")]
+            precompile_not_implemented: false,
         }
     }
 
     /// Sets the database.
     ///
     /// Note that this will ignore the previous `error` if set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a block hash provider is set, as it is bound to the previous database type.
     #[inline]
     pub fn with_db<ODB: Database>(self, db: ODB) -> InnerEvmContext<ODB> {
+        assert!(
+            self.block_hash_provider.is_none(),
+            "the block hash provider must be set after the database"
+        );
         InnerEvmContext {
             env: self.env,
             journaled_state: self.journaled_state,
             db,
             error: Ok(()),
             valid_authorizations: Default::default(),
+            block_hash_provider: None,
             #[cfg(any(feature = "optimism", feature = "scroll"))]
             l1_block_info: self.l1_block_info,
+            #[cfg(feature = "scroll")]
+            precompile_not_implemented: self.precompile_not_implemented,
         }
     }
 
@@ -139,10 +168,17 @@ impl<DB: Database> InnerEvmContext<DB> {
         core::mem::replace(&mut self.error, Ok(()))
     }
 
-    /// Fetch block hash from database.
+    /// Fetch block hash from the block hash provider, which may query the database.
+    ///
+    /// Block numbers outside of the window served by the spec return zero.
     #[inline]
     pub fn block_hash(&mut self, number: u64) -> Result<B256, EVMError<DB::Error>> {
-        self.db.block_hash(number).map_err(EVMError::Database)
+        let spec_id = self.spec_id();
+        match &mut self.block_hash_provider {
+            Some(provider) => provider.block_hash(spec_id, &self.env, &mut self.db, number),
+            None => SpecBlockHashProvider.block_hash(spec_id, &self.env, &mut self.db, number),
+        }
+        .map_err(EVMError::Database)
     }
 
     /// Mark account as touched as only touched accounts will be added to state.
@@ -1,25 +1,53 @@
 //! [Database] implementations.
 
-#[cfg(any(feature = "alloydb", feature = "ethersdb"))]
+#[cfg(any(feature = "alloydb", feature = "ethersdb", feature = "asyncdb"))]
 mod utils;
 
 #[cfg(feature = "alloydb")]
 mod alloydb;
+#[cfg(feature = "asyncdb")]
+pub mod async_db;
+#[cfg(feature = "scroll")]
+pub mod code_info_db;
 pub mod emptydb;
 #[cfg(feature = "ethersdb")]
 mod ethersdb;
+#[cfg(feature = "std")]
+pub mod file_db;
+#[cfg(feature = "serde")]
+pub mod genesis;
 pub mod in_memory_db;
+#[cfg(feature = "std")]
+pub mod metrics_db;
+pub mod overlay_db;
+pub mod recording_db;
+pub mod snapshot_db;
 pub mod states;
 
 pub use crate::primitives::db::*;
 #[cfg(feature = "alloydb")]
 pub use alloydb::AlloyDB;
+#[cfg(feature = "asyncdb")]
+pub use async_db::{AsyncDatabase, PrefetchDB, SyncDB};
+#[cfg(feature = "scroll")]
+pub use code_info_db::{CodeInfo, CodeInfoDB};
 pub use emptydb::{EmptyDB, EmptyDBTyped};
 #[cfg(feature = "ethersdb")]
 pub use ethersdb::EthersDB;
+#[cfg(feature = "std")]
+pub use file_db::FileDB;
+#[cfg(feature = "serde")]
+pub use genesis::{GenesisAccount, GenesisAlloc};
 pub use in_memory_db::*;
+#[cfg(feature = "std")]
+pub use metrics_db::{CacheProbe, DatabaseRequest, DatabaseStats, MethodStats, MetricsDB};
+pub use overlay_db::{
+    AccountOverride, BlockOverrides, OverlayDB, StateOverride, StateOverrideError,
+};
+pub use recording_db::{DbFixture, RecordingDB, ReplayDB, ReplayError};
+pub use snapshot_db::{NotABranchError, SnapshotDB};
 pub use states::{
     AccountRevert, AccountStatus, BundleAccount, BundleState, CacheState, DBBox,
-    OriginalValuesKnown, PlainAccount, RevertToSlot, State, StateBuilder, StateDBBox,
+    OriginalValuesKnown, PlainAccount, RevertToSlot, State, StateBuilder, StateDBBox, StateRoot,
     StorageWithOriginalValues, TransitionAccount, TransitionState,
 };
@@ -0,0 +1,447 @@
+//! Asynchronous database and a synchronous adapter that prefetches data in batches.
+
+use crate::{
+    db::{AccountState, CacheDB, DatabaseRef, DbAccount},
+    primitives::{
+        AccessListItem, AccountInfo, Address, Bytecode, HashSet, B256, KECCAK_EMPTY, U256,
+    },
+    Database,
+};
+use core::{
+    future::{poll_fn, Future},
+    mem,
+    task::{Context, Poll, Waker},
+};
+use std::{
+    boxed::Box,
+    sync::{Arc, Mutex, PoisonError},
+    task::Wake,
+    vec::Vec,
+};
+use tokio::runtime::{Handle, Runtime};
+
+use super::utils::HandleOrRuntime;
+
+/// Asynchronous version of [DatabaseRef].
+///
+/// Requests take `&self` so that they can run concurrently.
+pub trait AsyncDatabase {
+    /// The database error type.
+    type Error: Send;
+
+    /// Get basic account information.
+    fn basic_async(
+        &self,
+        address: Address,
+    ) -> impl Future<Output = Result<Option<AccountInfo>, Self::Error>> + Send;
+
+    /// Get account code by its hash.
+    fn code_by_hash_async(
+        &self,
+        code_hash: B256,
+    ) -> impl Future<Output = Result<Bytecode, Self::Error>> + Send;
+
+    /// Get storage value of address at index.
+    fn storage_async(
+        &self,
+        address: Address,
+        index: U256,
+    ) -> impl Future<Output = Result<U256, Self::Error>> + Send;
+
+    /// Get block hash by block number.
+    fn block_hash_async(
+        &self,
+        number: u64,
+    ) -> impl Future<Output = Result<B256, Self::Error>> + Send;
+}
+
+/// Synchronous [Database] over an [AsyncDatabase], every request blocks on the tokio runtime.
+///
+/// Wrapped in a [PrefetchDB], only the requests that were not prefetched reach it.
+#[derive(Debug)]
+pub struct SyncDB<DB> {
+    /// The asynchronous database.
+    pub db: DB,
+    /// handle to the tokio runtime
+    rt: HandleOrRuntime,
+}
+
+impl<DB> SyncDB<DB> {
+    /// Create a new SyncDB instance using the current tokio runtime.
+    ///
+    /// Returns `None` if no tokio runtime is available or if the current runtime is a current-thread runtime.
+    pub fn new(db: DB) -> Option<Self> {
+        let handle = Handle::try_current().ok()?;
+        match handle.runtime_flavor() {
+            tokio::runtime::RuntimeFlavor::CurrentThread => None,
+            _ => Some(Self::with_handle(db, handle)),
+        }
+    }
+
+    /// Create a new SyncDB instance with a runtime.
+    pub fn with_runtime(db: DB, runtime: Runtime) -> Self {
+        Self {
+            db,
+            rt: HandleOrRuntime::Runtime(runtime),
+        }
+    }
+
+    /// Create a new SyncDB instance with a runtime handle.
+    pub fn with_handle(db: DB, handle: Handle) -> Self {
+        Self {
+            db,
+            rt: HandleOrRuntime::Handle(handle),
+        }
+    }
+
+    /// Internal utility function that allows us to block on a future regardless of the runtime flavor.
+    #[inline]
+    fn block_on<F>(&self, f: F) -> F::Output
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        self.rt.block_on(f)
+    }
+}
+
+impl<DB: AsyncDatabase + Sync> DatabaseRef for SyncDB<DB> {
+    type Error = DB::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.block_on(self.db.basic_async(address))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.block_on(self.db.code_by_hash_async(code_hash))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.block_on(self.db.storage_async(address, index))
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.block_on(self.db.block_hash_async(number))
+    }
+}
+
+impl<DB: AsyncDatabase + Sync> Database for SyncDB<DB> {
+    type Error = DB::Error;
+
+    #[inline]
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        <Self as DatabaseRef>::basic_ref(self, address)
+    }
+
+    #[inline]
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        <Self as DatabaseRef>::code_by_hash_ref(self, code_hash)
+    }
+
+    #[inline]
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        <Self as DatabaseRef>::storage_ref(self, address, index)
+    }
+
+    #[inline]
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        <Self as DatabaseRef>::block_hash_ref(self, number)
+    }
+}
+
+/// [CacheDB] over an [AsyncDatabase], filled in batches with [PrefetchDB::prefetch].
+///
+/// Misses during execution fall back to a single request on the [SyncDB].
+pub type PrefetchDB<DB> = CacheDB<SyncDB<DB>>;
+
+impl<DB: AsyncDatabase + Sync> CacheDB<SyncDB<DB>> {
+    /// Loads the accounts, their code and the storage slots of the access list concurrently.
+    ///
+    /// The access list can be the one of the transaction, the one generated by
+    /// [crate::Evm::create_access_list] or the accesses of a previous run recorded by a
+    /// [crate::db::RecordingDB]. Accounts and slots that are already cached are not requested,
+    /// nor are slots of cached accounts whose storage is known to be cleared.
+    pub fn prefetch(&mut self, access_list: &[AccessListItem]) -> Result<(), DB::Error> {
+        let mut addresses = HashSet::new();
+        let mut slots = HashSet::new();
+        for item in access_list {
+            let cached = self.accounts.get(&item.address);
+            if cached.is_none() {
+                addresses.insert(item.address);
+            }
+            if cached.is_some_and(|account| {
+                matches!(
+                    account.account_state,
+                    AccountState::StorageCleared | AccountState::NotExisting
+                )
+            }) {
+                continue;
+            }
+            for key in &item.storage_keys {
+                let index = U256::from_be_bytes(key.0);
+                if !cached.is_some_and(|account| account.storage.contains_key(&index)) {
+                    slots.insert((item.address, index));
+                }
+            }
+        }
+
+        let db = &self.db;
+        let (accounts, storage) = db.block_on(async {
+            let accounts = join_all(
+                addresses
+                    .into_iter()
+                    .map(|address| async move { (address, db.db.basic_async(address).await) }),
+            );
+            let storage = join_all(slots.into_iter().map(|(address, index)| async move {
+                (address, index, db.db.storage_async(address, index).await)
+            }));
+            tokio::join!(accounts, storage)
+        });
+        let accounts = accounts
+            .into_iter()
+            .map(|(address, info)| Ok((address, info?)))
+            .collect::<Result<Vec<_>, DB::Error>>()?;
+
+        // code is only requested for accounts that did not return it.
+        let code_hashes: HashSet<B256> = accounts
+            .iter()
+            .filter_map(|(_, info)| info.as_ref())
+            .filter(|info| info.code.is_none() && info.code_hash != KECCAK_EMPTY)
+            .map(|info| info.code_hash)
+            .filter(|code_hash| !self.contracts.contains_key(code_hash))
+            .collect();
+        let contracts = db.block_on(join_all(code_hashes.into_iter().map(
+            |code_hash| async move { (code_hash, db.db.code_by_hash_async(code_hash).await) },
+        )));
+        for (code_hash, code) in contracts {
+            self.contracts.insert(code_hash, code?);
+        }
+
+        for (address, info) in accounts {
+            match info {
+                Some(mut info) => {
+                    if info.code.is_none() {
+                        info.code = self.contracts.get(&info.code_hash).cloned();
+                    }
+                    self.insert_account_info(address, info);
+                }
+                None => {
+                    self.accounts.insert(address, DbAccount::new_not_existing());
+                }
+            }
+        }
+        for (address, index, value) in storage {
+            let value = value?;
+            if let Some(account) = self.accounts.get_mut(&address) {
+                account.storage.entry(index).or_insert(value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Polls all futures concurrently and returns their outputs once all of them completed.
+///
+/// Every future gets its own waker, so only the futures that were woken are polled again.
+async fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> Vec<F::Output> {
+    let mut futures: Vec<_> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+    let woken = Arc::new(Woken {
+        indices: Mutex::new((0..futures.len()).collect()),
+        waker: Mutex::new(None),
+    });
+    let wakers: Vec<Waker> = (0..futures.len())
+        .map(|index| {
+            Waker::from(Arc::new(FutureWaker {
+                index,
+                woken: woken.clone(),
+            }))
+        })
+        .collect();
+    let mut pending = futures.len();
+    poll_fn(|cx| {
+        *woken.waker.lock().unwrap_or_else(PoisonError::into_inner) = Some(cx.waker().clone());
+        let indices = mem::take(&mut *woken.indices.lock().unwrap_or_else(PoisonError::into_inner));
+        for index in indices {
+            if outputs[index].is_some() {
+                continue;
+            }
+            let mut cx = Context::from_waker(&wakers[index]);
+            if let Poll::Ready(output) = futures[index].as_mut().poll(&mut cx) {
+                outputs[index] = Some(output);
+                pending -= 1;
+            }
+        }
+        if pending == 0 {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+    outputs.into_iter().flatten().collect()
+}
+
+/// Futures of a [join_all] that were woken since they were last polled.
+struct Woken {
+    indices: Mutex<Vec<usize>>,
+    /// Waker of the [join_all] future.
+    waker: Mutex<Option<Waker>>,
+}
+
+/// Waker of a single future of a [join_all].
+struct FutureWaker {
+    index: usize,
+    woken: Arc<Woken>,
+}
+
+impl Wake for FutureWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken
+            .indices
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(self.index);
+        if let Some(waker) = &*self
+            .woken
+            .waker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+        {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::InMemoryDB,
+        primitives::{Bytes, TxKind},
+        test_utils::{test_evm_builder, TEST_CALLER, TEST_CONTRACT},
+    };
+    use core::{
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// [InMemoryDB] that counts the requests and yields before answering.
+    #[derive(Default)]
+    struct CountingDB {
+        db: InMemoryDB,
+        requests: AtomicUsize,
+    }
+
+    impl CountingDB {
+        async fn request<T>(
+            &self,
+            f: impl FnOnce(&InMemoryDB) -> Result<T, Infallible>,
+        ) -> Result<T, Infallible> {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            tokio::task::yield_now().await;
+            f(&self.db)
+        }
+    }
+
+    impl AsyncDatabase for CountingDB {
+        type Error = Infallible;
+
+        async fn basic_async(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+            // code is requested separately, as with most remote databases.
+            let info = self.request(|db| db.basic_ref(address)).await?;
+            Ok(info.map(|info| info.without_code()))
+        }
+
+        async fn code_by_hash_async(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+            self.request(|db| db.code_by_hash_ref(code_hash)).await
+        }
+
+        async fn storage_async(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+            self.request(|db| db.storage_ref(address, index)).await
+        }
+
+        async fn block_hash_async(&self, number: u64) -> Result<B256, Self::Error> {
+            self.request(|db| db.block_hash_ref(number)).await
+        }
+    }
+
+    #[test]
+    fn test_prefetch() {
+        let mut db = CountingDB::default();
+        // SLOAD(1), SLOAD(2)
+        db.db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                0x60, 0x01, 0x54, 0x60, 0x02, 0x54, 0x00,
+            ]))),
+        );
+        db.db
+            .insert_account_storage(TEST_CONTRACT, U256::from(1), U256::from(3))
+            .unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let mut db = PrefetchDB::new(SyncDB::with_runtime(db, runtime));
+        db.prefetch(&[
+            AccessListItem {
+                address: TEST_CALLER,
+                storage_keys: Vec::new(),
+            },
+            AccessListItem {
+                address: Address::ZERO,
+                storage_keys: Vec::new(),
+            },
+            AccessListItem {
+                address: TEST_CONTRACT,
+                storage_keys: vec![B256::with_last_byte(1)],
+            },
+        ])
+        .unwrap();
+        // three accounts, one code and one slot.
+        assert_eq!(db.db.db.requests.load(Ordering::Relaxed), 5);
+        assert_eq!(
+            db.accounts[&TEST_CONTRACT].storage[&U256::from(1)],
+            U256::from(3)
+        );
+
+        let mut evm = test_evm_builder(&mut db, TxKind::Call(TEST_CONTRACT)).build();
+        assert!(evm.transact().unwrap().result.is_success());
+        drop(evm);
+        // only the slot that was not prefetched is requested, scroll also loads the L1 gas
+        // price oracle.
+        #[cfg(not(feature = "scroll"))]
+        assert_eq!(db.db.db.requests.load(Ordering::Relaxed), 6);
+    }
+
+    #[test]
+    fn test_prefetch_cleared_storage() {
+        let mut db = CountingDB::default();
+        db.db
+            .insert_account_storage(TEST_CONTRACT, U256::from(1), U256::from(3))
+            .unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let mut db = PrefetchDB::new(SyncDB::with_runtime(db, runtime));
+        // storage of the cached account is cleared, so the stale remote slot is not used.
+        db.replace_account_storage(TEST_CONTRACT, Default::default())
+            .unwrap();
+        db.prefetch(&[AccessListItem {
+            address: TEST_CONTRACT,
+            storage_keys: vec![B256::with_last_byte(1)],
+        }])
+        .unwrap();
+        assert_eq!(db.db.db.requests.load(Ordering::Relaxed), 1);
+        assert_eq!(
+            db.storage(TEST_CONTRACT, U256::from(1)).unwrap(),
+            U256::ZERO
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use crate::primitives::{
+    hash_map::Entry, Account, AccountInfo, Address, Bytecode, HashMap, B256, KECCAK_EMPTY, U256,
+};
+use crate::{Database, DatabaseCommit};
+
+/// Code derived fields of an [AccountInfo] that are not part of the Ethereum state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodeInfo {
+    /// Size of the original bytecode.
+    pub code_size: usize,
+    /// Poseidon hash of the original bytecode.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    pub poseidon_code_hash: B256,
+}
+
+impl CodeInfo {
+    /// Code info of the empty bytecode.
+    pub const EMPTY: Self = Self {
+        code_size: 0,
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        poseidon_code_hash: crate::primitives::POSEIDON_EMPTY,
+    };
+
+    /// Derives the code info from the bytecode.
+    pub fn new(code: &Bytecode) -> Self {
+        Self {
+            code_size: code.len(),
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            poseidon_code_hash: code.poseidon_hash_slow(),
+        }
+    }
+
+    /// Returns `true` if the account info is missing any of the code derived fields.
+    ///
+    /// Accounts with empty code are never considered incomplete.
+    pub fn is_missing(info: &AccountInfo) -> bool {
+        if info.code_hash == KECCAK_EMPTY || info.code_hash.is_zero() {
+            return false;
+        }
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "scroll-poseidon-codehash")] {
+                info.code_size == 0
+                    || info.poseidon_code_hash.is_zero()
+                    || info.poseidon_code_hash == crate::primitives::POSEIDON_EMPTY
+            } else {
+                info.code_size == 0
+            }
+        }
+    }
+
+    /// Sets the code derived fields of the account info.
+    pub fn apply(&self, info: &mut AccountInfo) {
+        info.code_size = self.code_size;
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        {
+            info.poseidon_code_hash = self.poseidon_code_hash;
+        }
+    }
+}
+
+/// A [Database] wrapper that fills in `code_size` and `poseidon_code_hash` of loaded accounts.
+///
+/// Generic backends like `AlloyDB` or `EthersDB` only know about the Ethereum state and leave these
+/// fields at their default values. This wrapper fetches the code by its hash when the fields are
+/// missing and derives them, keeping a code hash index so each bytecode is hashed only once.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodeInfoDB<DB> {
+    /// Code info indexed by the keccak code hash.
+    pub code_info: HashMap<B256, CodeInfo>,
+    /// The underlying database.
+    pub db: DB,
+}
+
+impl<DB: Default> Default for CodeInfoDB<DB> {
+    fn default() -> Self {
+        Self::new(DB::default())
+    }
+}
+
+impl<DB> CodeInfoDB<DB> {
+    /// Wraps the database with an empty code info index.
+    pub fn new(db: DB) -> Self {
+        Self {
+            code_info: HashMap::new(),
+            db,
+        }
+    }
+
+    /// Consumes the wrapper and returns the underlying database.
+    pub fn into_inner(self) -> DB {
+        self.db
+    }
+}
+
+impl<DB: Database> CodeInfoDB<DB> {
+    /// Fills in the code derived fields of the account info if they are missing.
+    ///
+    /// If the account has no code attached, it is fetched from the underlying database and
+    /// attached to the account so it does not need to be loaded again.
+    pub fn fill_code_info(&mut self, info: &mut AccountInfo) -> Result<(), DB::Error> {
+        if info.code_hash == KECCAK_EMPTY || info.code_hash.is_zero() {
+            CodeInfo::EMPTY.apply(info);
+            return Ok(());
+        }
+        if !CodeInfo::is_missing(info) {
+            return Ok(());
+        }
+        let code_info = match self.code_info.entry(info.code_hash) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let code_info = match &info.code {
+                    Some(code) if !code.is_empty() => CodeInfo::new(code),
+                    _ => {
+                        let code = self.db.code_by_hash(info.code_hash)?;
+                        let code_info = CodeInfo::new(&code);
+                        info.code = Some(code);
+                        code_info
+                    }
+                };
+                *entry.insert(code_info)
+            }
+        };
+        code_info.apply(info);
+        Ok(())
+    }
+}
+
+impl<DB: Database> Database for CodeInfoDB<DB> {
+    type Error = DB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let Some(mut info) = self.db.basic(address)? else {
+            return Ok(None);
+        };
+        self.fill_code_info(&mut info)?;
+        Ok(Some(info))
+    }
+
+    #[inline]
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.db.code_by_hash(code_hash)
+    }
+
+    #[inline]
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.db.storage(address, index)
+    }
+
+    #[inline]
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        self.db.block_hash(number)
+    }
+}
+
+impl<DB: DatabaseCommit> DatabaseCommit for CodeInfoDB<DB> {
+    #[inline]
+    fn commit(&mut self, changes: HashMap<Address, Account>) {
+        self.db.commit(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{CacheDB, DbAccount, EmptyDB};
+    use crate::primitives::{bytes, Bytecode};
+
+    #[test]
+    fn test_fill_code_info() {
+        let code = Bytecode::new_raw(bytes!("6001600155"));
+        let code_hash = code.hash_slow();
+
+        let mut cache_db = CacheDB::new(EmptyDB::default());
+        cache_db.contracts.insert(code_hash, code.clone());
+        for i in 0..2 {
+            cache_db.accounts.insert(
+                Address::with_last_byte(i),
+                DbAccount::from(AccountInfo {
+                    code_hash,
+                    code_size: 0,
+                    code: None,
+                    ..Default::default()
+                }),
+            );
+        }
+
+        let mut db = CodeInfoDB::new(cache_db);
+        for i in 0..2 {
+            let info = db.basic(Address::with_last_byte(i)).unwrap().unwrap();
+            assert_eq!(info.code_size, code.len());
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            assert_eq!(info.poseidon_code_hash, code.poseidon_hash_slow());
+        }
+        // code is hashed once and indexed by its keccak hash.
+        assert_eq!(db.code_info.len(), 1);
+        assert_eq!(db.code_info[&code_hash], CodeInfo::new(&code));
+    }
+
+    #[test]
+    fn test_empty_code_info() {
+        let mut cache_db = CacheDB::new(EmptyDB::default());
+        cache_db.accounts.insert(
+            Address::ZERO,
+            DbAccount::from(AccountInfo::from_balance(U256::from(1))),
+        );
+
+        let mut db = CodeInfoDB::new(cache_db);
+        let info = db.basic(Address::ZERO).unwrap().unwrap();
+        assert_eq!(info.code_size, 0);
+        assert!(db.code_info.is_empty());
+    }
+}
@@ -0,0 +1,805 @@
+//! [Database] persisted in a local append-only file.
+
+use super::states::{
+    encoding::{feature_flags, take, take_slice},
+    reverts::Reverts,
+    DecodeError, OriginalValuesKnown, PlainStateReverts, StateChangeset,
+};
+use super::{in_memory_db::normalize_contract, BundleState, DatabaseCommit, DatabaseRef};
+#[cfg(feature = "scroll-poseidon-codehash")]
+use crate::primitives::PoseidonCodeHashCache;
+use crate::primitives::{
+    keccak256, Account, AccountInfo, Address, Bytecode, Bytes, HashMap, B256, U256,
+};
+use crate::Database;
+use core::convert::Infallible;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    vec::Vec,
+};
+
+/// Magic bytes at the start of the file, followed by the [feature_flags].
+const MAGIC: &[u8; 8] = b"REVMFDB1";
+
+/// Length of the header of each batch, the length of the records followed by its checksum.
+const BATCH_HEADER_LEN: usize = 8;
+
+/// Length of the checksum at the end of each batch.
+const CHECKSUM_LEN: usize = 8;
+
+/// A single change of the database, as written in the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Record {
+    /// Sets the account info, `None` removes the account.
+    Account(Address, Option<AccountInfo>),
+    /// Sets a storage slot, zero removes it.
+    Storage(Address, U256, U256),
+    /// Removes all storage slots of the account.
+    WipeStorage(Address),
+    /// Inserts a bytecode by its keccak hash.
+    Code {
+        code_hash: B256,
+        /// Poseidon hash of the bytecode, stored so that it is not recomputed when the file is
+        /// read.
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        poseidon_code_hash: B256,
+        code: Bytecode,
+    },
+    /// Sets the hash of a block.
+    BlockHash(u64, B256),
+}
+
+impl Record {
+    const ACCOUNT: u8 = 0;
+    const REMOVE_ACCOUNT: u8 = 1;
+    const STORAGE: u8 = 2;
+    const WIPE_STORAGE: u8 = 3;
+    const CODE: u8 = 4;
+    const BLOCK_HASH: u8 = 5;
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Account(address, Some(info)) => {
+                out.push(Self::ACCOUNT);
+                out.extend_from_slice(address.as_slice());
+                out.extend_from_slice(&info.balance.to_be_bytes::<32>());
+                out.extend_from_slice(&info.nonce.to_be_bytes());
+                out.extend_from_slice(info.code_hash.as_slice());
+                #[cfg(feature = "scroll")]
+                out.extend_from_slice(&(info.code_size as u64).to_be_bytes());
+                #[cfg(feature = "scroll-poseidon-codehash")]
+                out.extend_from_slice(info.poseidon_code_hash.as_slice());
+            }
+            Self::Account(address, None) => {
+                out.push(Self::REMOVE_ACCOUNT);
+                out.extend_from_slice(address.as_slice());
+            }
+            Self::Storage(address, index, value) => {
+                out.push(Self::STORAGE);
+                out.extend_from_slice(address.as_slice());
+                out.extend_from_slice(&index.to_be_bytes::<32>());
+                out.extend_from_slice(&value.to_be_bytes::<32>());
+            }
+            Self::WipeStorage(address) => {
+                out.push(Self::WIPE_STORAGE);
+                out.extend_from_slice(address.as_slice());
+            }
+            Self::Code {
+                code_hash,
+                #[cfg(feature = "scroll-poseidon-codehash")]
+                poseidon_code_hash,
+                code,
+            } => {
+                let bytes = code.original_byte_slice();
+                out.push(Self::CODE);
+                out.extend_from_slice(code_hash.as_slice());
+                #[cfg(feature = "scroll-poseidon-codehash")]
+                out.extend_from_slice(poseidon_code_hash.as_slice());
+                out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+                out.extend_from_slice(bytes);
+            }
+            Self::BlockHash(number, hash) => {
+                out.push(Self::BLOCK_HASH);
+                out.extend_from_slice(&number.to_be_bytes());
+                out.extend_from_slice(hash.as_slice());
+            }
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(match take::<1>(input)?[0] {
+            Self::ACCOUNT => {
+                let address = Address::new(take(input)?);
+                let balance = U256::from_be_bytes(take::<32>(input)?);
+                let nonce = u64::from_be_bytes(take(input)?);
+                let code_hash = B256::new(take(input)?);
+                let info = AccountInfo {
+                    balance,
+                    nonce,
+                    code_hash,
+                    #[cfg(feature = "scroll")]
+                    code_size: u64::from_be_bytes(take(input)?) as usize,
+                    #[cfg(feature = "scroll-poseidon-codehash")]
+                    poseidon_code_hash: B256::new(take(input)?),
+                    code: None,
+                };
+                Self::Account(address, Some(info))
+            }
+            Self::REMOVE_ACCOUNT => Self::Account(Address::new(take(input)?), None),
+            Self::STORAGE => Self::Storage(
+                Address::new(take(input)?),
+                U256::from_be_bytes(take::<32>(input)?),
+                U256::from_be_bytes(take::<32>(input)?),
+            ),
+            Self::WIPE_STORAGE => Self::WipeStorage(Address::new(take(input)?)),
+            Self::CODE => {
+                let code_hash = B256::new(take(input)?);
+                #[cfg(feature = "scroll-poseidon-codehash")]
+                let poseidon_code_hash = B256::new(take(input)?);
+                let len = u32::from_be_bytes(take(input)?) as usize;
+                let bytes = take_slice(input, len)?;
+                Self::Code {
+                    code_hash,
+                    #[cfg(feature = "scroll-poseidon-codehash")]
+                    poseidon_code_hash,
+                    code: Bytecode::new_raw(Bytes::copy_from_slice(bytes)),
+                }
+            }
+            Self::BLOCK_HASH => {
+                Self::BlockHash(u64::from_be_bytes(take(input)?), B256::new(take(input)?))
+            }
+            tag => {
+                return Err(DecodeError::InvalidTag {
+                    kind: "record",
+                    tag,
+                })
+            }
+        })
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A [Database] that keeps its state in memory and persists every change to a local file.
+///
+/// The file is an append-only log of batches of changes, each batch is checksummed and a
+/// batch that was not completely written, for example after a crash, is discarded when the
+/// file is opened. Any other corrupted batch fails the opening and the file is left untouched. [FileDB::compact]
+/// rewrites the file with only the current state.
+///
+/// Accounts, storage, bytecodes and block hashes are stored. Under the `scroll` feature the
+/// code size and Poseidon code hash of the accounts are stored as well, and bytecodes can be
+/// looked up by their Poseidon hash. Files written with different scroll features can not be
+/// opened.
+///
+/// Changes are applied with [DatabaseCommit::commit], [FileDB::apply_bundle] or
+/// [FileDB::apply_changeset], and blocks are unwound with [FileDB::revert].
+#[derive(Debug)]
+pub struct FileDB {
+    /// Path of the file.
+    path: PathBuf,
+    /// The file, opened for appending.
+    file: File,
+    /// Accounts, `code` is always `None` and bytecode can be found in `contracts`.
+    accounts: HashMap<Address, AccountInfo>,
+    /// Storage slots of each account, zero slots are not stored.
+    storage: HashMap<Address, HashMap<U256, U256>>,
+    /// Bytecodes by their keccak hash.
+    contracts: HashMap<B256, Bytecode>,
+    /// Keccak hashes of the bytecodes by their Poseidon hash.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    code_hashes_by_poseidon: HashMap<B256, B256>,
+    /// Poseidon hashes of the bytecodes, indexed by their keccak hash.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    poseidon_code_hashes: PoseidonCodeHashCache,
+    /// Block hashes by their number.
+    block_hashes: HashMap<u64, B256>,
+}
+
+impl FileDB {
+    /// Opens the database at the given path, creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        let mut db = Self {
+            path,
+            file,
+            accounts: HashMap::default(),
+            storage: HashMap::default(),
+            contracts: HashMap::default(),
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            code_hashes_by_poseidon: HashMap::default(),
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            poseidon_code_hashes: PoseidonCodeHashCache::default(),
+            block_hashes: HashMap::default(),
+        };
+        if data.is_empty() {
+            db.file.write_all(&header())?;
+            db.file.sync_all()?;
+            return Ok(db);
+        }
+        if data.get(..MAGIC.len() + 1) != Some(&header()[..]) {
+            return Err(invalid_data(
+                "not a database file or written with different scroll features",
+            ));
+        }
+
+        let mut offset = MAGIC.len() + 1;
+        while let Some(records) = read_batch(&data[offset..]) {
+            let (len, records) = records?;
+            for record in records {
+                db.apply_record(record);
+            }
+            offset += len;
+        }
+        if offset != data.len() {
+            // discard the batch that was not completely written.
+            db.file.set_len(offset as u64)?;
+        }
+        Ok(db)
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the bytecode with the given Poseidon hash.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    pub fn code_by_poseidon_hash(&self, poseidon_code_hash: B256) -> Option<&Bytecode> {
+        self.code_hashes_by_poseidon
+            .get(&poseidon_code_hash)
+            .and_then(|code_hash| self.contracts.get(code_hash))
+    }
+
+    /// Inserts the account info and its code.
+    pub fn insert_account_info(&mut self, address: Address, info: AccountInfo) -> io::Result<()> {
+        let mut records = Vec::with_capacity(2);
+        push_account(
+            &mut records,
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            &mut self.poseidon_code_hashes,
+            address,
+            info,
+        );
+        self.write(records)
+    }
+
+    /// Sets a storage slot of the account.
+    pub fn insert_account_storage(
+        &mut self,
+        address: Address,
+        slot: U256,
+        value: U256,
+    ) -> io::Result<()> {
+        self.write(vec![Record::Storage(address, slot, value)])
+    }
+
+    /// Sets the hash of a block.
+    pub fn insert_block_hash(&mut self, number: u64, hash: B256) -> io::Result<()> {
+        self.write(vec![Record::BlockHash(number, hash)])
+    }
+
+    /// Applies the state of a [BundleState].
+    ///
+    /// Reverts of the bundle are not applied, they can be taken with
+    /// [BundleState::take_all_reverts] before and passed to [FileDB::revert] to unwind it.
+    pub fn apply_bundle(&mut self, bundle: BundleState) -> io::Result<()> {
+        self.apply_changeset(bundle.into_plain_state(OriginalValuesKnown::Yes))
+    }
+
+    /// Applies a [StateChangeset] as returned by [BundleState::into_plain_state].
+    pub fn apply_changeset(&mut self, changeset: StateChangeset) -> io::Result<()> {
+        let mut records = Vec::new();
+        for (code_hash, code) in changeset.contracts {
+            records.push(Record::Code {
+                code_hash,
+                #[cfg(feature = "scroll-poseidon-codehash")]
+                poseidon_code_hash: self.poseidon_code_hashes.get_or_compute(code_hash, &code),
+                code,
+            });
+        }
+        for (address, info) in changeset.accounts {
+            records.push(Record::Account(address, info));
+        }
+        for storage in changeset.storage {
+            if storage.wipe_storage {
+                records.push(Record::WipeStorage(storage.address));
+            }
+            for (index, value) in storage.storage {
+                records.push(Record::Storage(storage.address, index, value));
+            }
+        }
+        self.write(records)
+    }
+
+    /// Unwinds the blocks of the [Reverts], as returned by [BundleState::take_all_reverts].
+    ///
+    /// Reverts must be of the latest blocks applied to the database, they are applied from the
+    /// latest block to the oldest one.
+    pub fn revert(&mut self, reverts: Reverts) -> io::Result<()> {
+        self.apply_plain_reverts(reverts.into_plain_state_reverts())
+    }
+
+    /// Unwinds the blocks of the [PlainStateReverts], from the latest block to the oldest one.
+    pub fn apply_plain_reverts(&mut self, reverts: PlainStateReverts) -> io::Result<()> {
+        let mut records = Vec::new();
+        for (accounts, storage) in reverts.accounts.into_iter().zip(reverts.storage).rev() {
+            for (address, info) in accounts {
+                records.push(Record::Account(
+                    address,
+                    info.map(AccountInfo::without_code),
+                ));
+            }
+            for storage in storage {
+                // wiped slots are part of the revert.
+                if storage.wiped {
+                    records.push(Record::WipeStorage(storage.address));
+                }
+                for (index, value) in storage.storage_revert {
+                    records.push(Record::Storage(
+                        storage.address,
+                        index,
+                        value.to_previous_value(),
+                    ));
+                }
+            }
+        }
+        self.write(records)
+    }
+
+    /// Rewrites the file with only the current state.
+    pub fn compact(&mut self) -> io::Result<()> {
+        let mut records = Vec::new();
+        #[cfg(not(feature = "scroll-poseidon-codehash"))]
+        records.extend(self.contracts.iter().map(|(code_hash, code)| Record::Code {
+            code_hash: *code_hash,
+            code: code.clone(),
+        }));
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        records.extend(self.code_hashes_by_poseidon.iter().map(
+            |(poseidon_code_hash, code_hash)| Record::Code {
+                code_hash: *code_hash,
+                poseidon_code_hash: *poseidon_code_hash,
+                code: self.contracts[code_hash].clone(),
+            },
+        ));
+        records.extend(
+            self.accounts
+                .iter()
+                .map(|(address, info)| Record::Account(*address, Some(info.clone()))),
+        );
+        for (address, storage) in &self.storage {
+            records.extend(
+                storage
+                    .iter()
+                    .map(|(index, value)| Record::Storage(*address, *index, *value)),
+            );
+        }
+        records.extend(
+            self.block_hashes
+                .iter()
+                .map(|(number, hash)| Record::BlockHash(*number, *hash)),
+        );
+
+        let mut data = header().to_vec();
+        encode_batch(&records, &mut data);
+        let tmp_path = tmp_path(&self.path);
+        {
+            let mut tmp = File::create(&tmp_path)?;
+            tmp.write_all(&data)?;
+            tmp.sync_all()?;
+        }
+        fs::rename(&tmp_path, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+
+    /// Writes a batch of records to the file and applies them.
+    fn write(&mut self, records: Vec<Record>) -> io::Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut data = Vec::new();
+        encode_batch(&records, &mut data);
+        self.file.write_all(&data)?;
+        self.file.sync_data()?;
+        for record in records {
+            self.apply_record(record);
+        }
+        Ok(())
+    }
+
+    fn apply_record(&mut self, record: Record) {
+        match record {
+            Record::Account(address, Some(info)) => {
+                self.accounts.insert(address, info);
+            }
+            Record::Account(address, None) => {
+                self.accounts.remove(&address);
+            }
+            Record::Storage(address, index, value) => {
+                let storage = self.storage.entry(address).or_default();
+                if value.is_zero() {
+                    storage.remove(&index);
+                } else {
+                    storage.insert(index, value);
+                }
+            }
+            Record::WipeStorage(address) => {
+                self.storage.remove(&address);
+            }
+            Record::Code {
+                code_hash,
+                #[cfg(feature = "scroll-poseidon-codehash")]
+                poseidon_code_hash,
+                code,
+            } => {
+                #[cfg(feature = "scroll-poseidon-codehash")]
+                {
+                    self.code_hashes_by_poseidon
+                        .insert(poseidon_code_hash, code_hash);
+                    self.poseidon_code_hashes
+                        .insert(code_hash, poseidon_code_hash);
+                }
+                self.contracts.insert(code_hash, code);
+            }
+            Record::BlockHash(number, hash) => {
+                self.block_hashes.insert(number, hash);
+            }
+        }
+    }
+}
+
+/// Returns the magic bytes followed by the format flags.
+fn header() -> [u8; MAGIC.len() + 1] {
+    let mut header = [0; MAGIC.len() + 1];
+    header[..MAGIC.len()].copy_from_slice(MAGIC);
+    header[MAGIC.len()] = feature_flags();
+    header
+}
+
+/// Returns the path the file is compacted to before it replaces the file at `path`.
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp_path = path.as_os_str().to_os_string();
+    tmp_path.push(".tmp");
+    tmp_path.into()
+}
+
+/// Encodes a batch as its header, the records and the checksum of the records.
+fn encode_batch(records: &[Record], out: &mut Vec<u8>) {
+    let start = out.len();
+    out.extend_from_slice(&[0; BATCH_HEADER_LEN]);
+    for record in records {
+        record.encode(out);
+    }
+    let len = ((out.len() - start - BATCH_HEADER_LEN) as u32).to_be_bytes();
+    out[start..start + 4].copy_from_slice(&len);
+    out[start + 4..start + BATCH_HEADER_LEN].copy_from_slice(&keccak256(len)[..4]);
+    let checksum = keccak256(&out[start + BATCH_HEADER_LEN..]);
+    out.extend_from_slice(&checksum[..CHECKSUM_LEN]);
+}
+
+/// Reads the batch at the start of the data, returns its encoded length and its records.
+///
+/// Returns `None` if the batch was not completely written, that is if it extends past the end
+/// of the data or if it is the last batch of the data and its checksum does not match. A
+/// corrupted header or a checksum mismatch of a batch followed by other data is an error.
+fn read_batch(data: &[u8]) -> Option<io::Result<(usize, Vec<Record>)>> {
+    let header = data.get(..BATCH_HEADER_LEN)?;
+    if keccak256(&header[..4])[..4] != header[4..] {
+        return Some(Err(invalid_data("batch header checksum mismatch")));
+    }
+    let len = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+    let end = BATCH_HEADER_LEN + len + CHECKSUM_LEN;
+    let batch = data.get(..end)?;
+    let payload = &batch[BATCH_HEADER_LEN..BATCH_HEADER_LEN + len];
+    if keccak256(payload)[..CHECKSUM_LEN] != batch[BATCH_HEADER_LEN + len..] {
+        if data.len() == end {
+            return None;
+        }
+        return Some(Err(invalid_data("batch checksum mismatch")));
+    }
+    let mut input = payload;
+    let mut records = Vec::new();
+    while !input.is_empty() {
+        match Record::decode(&mut input) {
+            Ok(record) => records.push(record),
+            Err(err) => return Some(Err(io::Error::new(io::ErrorKind::InvalidData, err))),
+        }
+    }
+    Some(Ok((end, records)))
+}
+
+/// Pushes the records of an account info and its code.
+fn push_account(
+    records: &mut Vec<Record>,
+    #[cfg(feature = "scroll-poseidon-codehash")] poseidon_code_hashes: &mut PoseidonCodeHashCache,
+    address: Address,
+    mut info: AccountInfo,
+) {
+    if let Some(code) = normalize_contract(
+        &mut info,
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        poseidon_code_hashes,
+    ) {
+        records.push(Record::Code {
+            code_hash: info.code_hash,
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            poseidon_code_hash: info.poseidon_code_hash,
+            code,
+        });
+    }
+    records.push(Record::Account(address, Some(info)));
+}
+
+impl DatabaseCommit for FileDB {
+    /// Commits the changes to the file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the changes can not be written to the file.
+    fn commit(&mut self, changes: HashMap<Address, Account>) {
+        let mut records = Vec::new();
+        for (address, account) in changes {
+            if !account.is_touched() {
+                continue;
+            }
+            if account.is_selfdestructed() {
+                records.push(Record::Account(address, None));
+                records.push(Record::WipeStorage(address));
+                continue;
+            }
+            if account.is_created() {
+                records.push(Record::WipeStorage(address));
+            }
+            push_account(
+                &mut records,
+                #[cfg(feature = "scroll-poseidon-codehash")]
+                &mut self.poseidon_code_hashes,
+                address,
+                account.info,
+            );
+            records.extend(
+                account
+                    .storage
+                    .into_iter()
+                    .filter(|(_, slot)| slot.is_changed())
+                    .map(|(index, slot)| Record::Storage(address, index, slot.present_value())),
+            );
+        }
+        self.write(records)
+            .expect("failed to write the changes to the database file");
+    }
+}
+
+impl DatabaseRef for FileDB {
+    type Error = Infallible;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        Ok(self.accounts.get(&address).cloned())
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        Ok(self.contracts.get(&code_hash).cloned().unwrap_or_default())
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        Ok(self
+            .storage
+            .get(&address)
+            .and_then(|storage| storage.get(&index))
+            .copied()
+            .unwrap_or_default())
+    }
+
+    /// Returns the inserted block hash, or the same hash as [super::EmptyDB] if there is none.
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        Ok(self
+            .block_hashes
+            .get(&number)
+            .copied()
+            .unwrap_or_else(|| keccak256(number.to_string().as_bytes())))
+    }
+}
+
+impl Database for FileDB {
+    type Error = Infallible;
+
+    #[inline]
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.basic_ref(address)
+    }
+
+    #[inline]
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.code_by_hash_ref(code_hash)
+    }
+
+    #[inline]
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.storage_ref(address, index)
+    }
+
+    #[inline]
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        self.block_hash_ref(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{states::bundle_state::BundleRetention, State},
+        test_utils::{test_call_commit, TEST_CALLER, TEST_CONTRACT},
+    };
+
+    /// Returns a path in the temporary directory that is removed when dropped.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("revm-file-db-{}-{name}", std::process::id()));
+            let _ = fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// Deploys a contract that increments slot 0 when called.
+    fn deploy(db: &mut FileDB) {
+        // SSTORE(0, SLOAD(0) + 1)
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x00,
+            ]))),
+        )
+        .unwrap();
+    }
+
+    fn increment<DB: Database<Error = Infallible> + DatabaseCommit>(db: DB) {
+        assert!(test_call_commit(db).unwrap().is_success());
+    }
+
+    #[test]
+    fn test_file_db_reopen() {
+        let path = TempPath::new("reopen");
+        let mut db = FileDB::open(&path.0).unwrap();
+        deploy(&mut db);
+        increment(&mut db);
+        db.insert_block_hash(1, B256::with_last_byte(1)).unwrap();
+        drop(db);
+
+        // a batch that was not completely written is discarded.
+        let len = fs::metadata(&path.0).unwrap().len();
+        OpenOptions::new()
+            .append(true)
+            .open(&path.0)
+            .unwrap()
+            .write_all(&[0, 0, 0, 100, 1, 2])
+            .unwrap();
+
+        let mut db = FileDB::open(&path.0).unwrap();
+        assert_eq!(fs::metadata(&path.0).unwrap().len(), len);
+        assert_eq!(
+            db.storage(TEST_CONTRACT, U256::ZERO).unwrap(),
+            U256::from(1)
+        );
+        assert_eq!(db.block_hash(1).unwrap(), B256::with_last_byte(1));
+        let info = db.basic(TEST_CONTRACT).unwrap().unwrap();
+        #[cfg(feature = "scroll")]
+        assert_eq!(info.code_size, 10);
+        assert_eq!(db.code_by_hash(info.code_hash).unwrap().len(), 10);
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        assert!(db.code_by_poseidon_hash(info.poseidon_code_hash).is_some());
+
+        // compaction keeps the state.
+        increment(&mut db);
+        db.compact().unwrap();
+        assert!(fs::metadata(&path.0).unwrap().len() < len);
+        let db = FileDB::open(&path.0).unwrap();
+        assert_eq!(
+            db.storage_ref(TEST_CONTRACT, U256::ZERO).unwrap(),
+            U256::from(2)
+        );
+    }
+
+    #[test]
+    fn test_file_db_bundle_and_revert() {
+        let path = TempPath::new("bundle");
+        let mut db = FileDB::open(&path.0).unwrap();
+        deploy(&mut db);
+
+        let mut state = State::builder()
+            .with_database_ref(&db)
+            .with_bundle_update()
+            .build();
+        for _ in 0..2 {
+            increment(&mut state);
+            state.merge_transitions(BundleRetention::Reverts);
+        }
+        let mut bundle = state.take_bundle();
+        let mut reverts = bundle.take_all_reverts();
+        db.apply_bundle(bundle).unwrap();
+        assert_eq!(
+            db.storage(TEST_CONTRACT, U256::ZERO).unwrap(),
+            U256::from(2)
+        );
+
+        // unwinds the latest block.
+        let latest = reverts.pop().unwrap();
+        db.revert(Reverts::new(vec![latest])).unwrap();
+        assert_eq!(
+            db.storage(TEST_CONTRACT, U256::ZERO).unwrap(),
+            U256::from(1)
+        );
+        db.revert(reverts).unwrap();
+        drop(db);
+        let mut db = FileDB::open(&path.0).unwrap();
+        assert_eq!(db.storage(TEST_CONTRACT, U256::ZERO).unwrap(), U256::ZERO);
+        // the caller did not exist before the first block.
+        assert_eq!(db.basic(TEST_CALLER).unwrap(), None);
+    }
+
+    #[test]
+    fn test_file_db_checksum_mismatch() {
+        let path = TempPath::new("checksum");
+        let mut db = FileDB::open(&path.0).unwrap();
+        deploy(&mut db);
+        increment(&mut db);
+        drop(db);
+        let len = fs::metadata(&path.0).unwrap().len();
+
+        // a complete last batch with a wrong checksum was not completely written.
+        let mut batch = Vec::new();
+        encode_batch(&[Record::BlockHash(1, B256::ZERO)], &mut batch);
+        *batch.last_mut().unwrap() ^= 1;
+        let mut file = OpenOptions::new().append(true).open(&path.0).unwrap();
+        file.write_all(&batch).unwrap();
+        drop(file);
+        FileDB::open(&path.0).unwrap();
+        assert_eq!(fs::metadata(&path.0).unwrap().len(), len);
+
+        // a corrupted batch followed by other batches is an error, the file is kept.
+        let data = fs::read(&path.0).unwrap();
+        let mut corrupted = data.clone();
+        corrupted[MAGIC.len() + 1 + BATCH_HEADER_LEN] ^= 1;
+        fs::write(&path.0, &corrupted).unwrap();
+        let err = FileDB::open(&path.0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read(&path.0).unwrap(), corrupted);
+
+        // so is a corrupted length, even if the batch would extend past the end of the file.
+        let mut corrupted = data;
+        corrupted[MAGIC.len() + 1] ^= 1;
+        fs::write(&path.0, &corrupted).unwrap();
+        let err = FileDB::open(&path.0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read(&path.0).unwrap(), corrupted);
+    }
+
+    #[test]
+    fn test_file_db_tmp_path() {
+        assert_eq!(tmp_path(Path::new("db")), Path::new("db.tmp"));
+        assert_eq!(tmp_path(Path::new("db.tmp")), Path::new("db.tmp.tmp"));
+    }
+}
@@ -0,0 +1,255 @@
+//! Conversion between the database state and the genesis `alloc` JSON format, as used by
+//! genesis files, state tests and t8n tools.
+
+use super::{AccountState, BundleState, CacheDB, CacheState};
+use crate::primitives::{AccountInfo, Address, Bytecode, Bytes, B256, KECCAK_EMPTY, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Accounts of a genesis `alloc`, ordered by address.
+pub type GenesisAlloc = BTreeMap<Address, GenesisAccount>;
+
+/// Account of a genesis `alloc`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisAccount {
+    /// Balance of the account.
+    #[serde(default)]
+    pub balance: U256,
+    /// Nonce of the account.
+    #[serde(default, with = "serde_hex_u64")]
+    pub nonce: u64,
+    /// Bytecode of the account.
+    #[serde(default, skip_serializing_if = "<[u8]>::is_empty")]
+    pub code: Bytes,
+    /// Storage of the account, slots that are not set are zero.
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        with = "serde_storage"
+    )]
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl GenesisAccount {
+    /// Creates the genesis account from the account info, storage slots that are zero are
+    /// skipped.
+    ///
+    /// The code of the info is used, it has to be loaded beforehand.
+    pub fn new(info: &AccountInfo, storage: impl IntoIterator<Item = (U256, U256)>) -> Self {
+        Self {
+            balance: info.balance,
+            nonce: info.nonce,
+            code: info
+                .code
+                .as_ref()
+                .map(Bytecode::original_bytes)
+                .unwrap_or_default(),
+            storage: storage
+                .into_iter()
+                .filter(|(_, value)| !value.is_zero())
+                .collect(),
+        }
+    }
+
+    /// Returns the account info, with the code hashes computed from the code.
+    pub fn account_info(&self) -> AccountInfo {
+        let code = Bytecode::new_raw(self.code.clone());
+        let code_hash = if code.is_empty() {
+            KECCAK_EMPTY
+        } else {
+            code.hash_slow()
+        };
+        #[allow(unused_mut)]
+        let mut info = AccountInfo::new(self.balance, self.nonce, code_hash, code);
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        if let Some(code) = &info.code {
+            info.poseidon_code_hash = code.poseidon_hash_slow();
+        }
+        info
+    }
+}
+
+impl<ExtDB> CacheDB<ExtDB> {
+    /// Inserts the accounts of the genesis `alloc`, replacing their info and storage.
+    pub fn insert_genesis_alloc(&mut self, alloc: &GenesisAlloc) {
+        for (address, account) in alloc {
+            self.insert_account_info(*address, account.account_info());
+            let db_account = self.accounts.get_mut(address).unwrap();
+            db_account.account_state = AccountState::StorageCleared;
+            db_account.storage = account
+                .storage
+                .iter()
+                .map(|(index, value)| (*index, *value))
+                .collect();
+        }
+    }
+
+    /// Returns the cached accounts as a genesis `alloc`.
+    ///
+    /// Only accounts and storage slots loaded in the cache are included, accounts that do not
+    /// exist are skipped.
+    pub fn genesis_alloc(&self) -> GenesisAlloc {
+        self.accounts
+            .iter()
+            .filter_map(|(address, account)| {
+                let mut info = account.info()?;
+                if info.code.is_none() {
+                    info.code = self.contracts.get(&info.code_hash).cloned();
+                }
+                let storage = account.storage.iter().map(|(k, v)| (*k, *v));
+                Some((*address, GenesisAccount::new(&info, storage)))
+            })
+            .collect()
+    }
+}
+
+impl CacheState {
+    /// Inserts the accounts of the genesis `alloc`, replacing their info and storage.
+    pub fn insert_genesis_alloc(&mut self, alloc: &GenesisAlloc) {
+        for (address, account) in alloc {
+            let info = account.account_info();
+            if let Some(code) = info.code.as_ref().filter(|code| !code.is_empty()) {
+                self.contracts.insert(info.code_hash, code.clone());
+            }
+            let storage = account
+                .storage
+                .iter()
+                .map(|(index, value)| (*index, *value))
+                .collect();
+            self.insert_account_with_storage(*address, info, storage);
+        }
+    }
+}
+
+impl BundleState {
+    /// Returns the accounts of the bundle as a genesis `alloc`.
+    ///
+    /// Only the storage slots changed in the bundle are included, accounts that were destroyed
+    /// are skipped.
+    pub fn genesis_alloc(&self) -> GenesisAlloc {
+        self.state
+            .iter()
+            .filter_map(|(address, account)| {
+                let mut info = account.info.clone()?;
+                if info.code.is_none() {
+                    info.code = self.contracts.get(&info.code_hash).cloned();
+                }
+                let storage = account
+                    .storage
+                    .iter()
+                    .map(|(index, slot)| (*index, slot.present_value));
+                Some((*address, GenesisAccount::new(&info, storage)))
+            })
+            .collect()
+    }
+}
+
+/// Serializes the nonce as a hex string.
+mod serde_hex_u64 {
+    use crate::primitives::alloy_primitives::U64;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        U64::from(*value).serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        U64::deserialize(deserializer).map(|value| value.to())
+    }
+}
+
+/// Serializes the storage slots as 32 byte hex strings, deserializes them from any hex string.
+mod serde_storage {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        storage: &BTreeMap<U256, U256>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            storage
+                .iter()
+                .map(|(index, value)| (B256::from(*index), B256::from(*value))),
+        )
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<U256, U256>, D::Error> {
+        BTreeMap::deserialize(deserializer)
+    }
+}
+
+#[cfg(all(test, feature = "serde-json"))]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{states::bundle_state::BundleRetention, EmptyDB, State},
+        test_utils::{test_call_commit, TEST_CALLER, TEST_CONTRACT},
+        Database, DatabaseCommit,
+    };
+
+    const ALLOC: &str = r#"{
+        "0x2000000000000000000000000000000000000000": {
+            "balance": "0x0",
+            "nonce": "0x1",
+            "code": "0x60005460010160005500",
+            "storage": { "0x00": "0x05" }
+        },
+        "0x1000000000000000000000000000000000000000": {
+            "balance": "0xde0b6b3a7640000"
+        }
+    }"#;
+
+    fn increment<DB: Database + DatabaseCommit>(db: DB) {
+        assert!(test_call_commit(db).is_ok_and(|result| result.is_success()));
+    }
+
+    #[test]
+    fn test_cache_db_genesis_alloc() {
+        let alloc: GenesisAlloc = serde_json::from_str(ALLOC).unwrap();
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_genesis_alloc(&alloc);
+
+        let info = db.basic(TEST_CONTRACT).unwrap().unwrap();
+        let code = Bytecode::new_raw(alloc[&TEST_CONTRACT].code.clone());
+        assert_eq!(info.code_hash, code.hash_slow());
+        #[cfg(feature = "scroll")]
+        assert_eq!(info.code_size, 10);
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        assert_eq!(info.poseidon_code_hash, code.poseidon_hash_slow());
+
+        increment(&mut db);
+        let exported = db.genesis_alloc();
+        assert_eq!(exported[&TEST_CONTRACT].storage[&U256::ZERO], U256::from(6));
+        assert_eq!(
+            serde_json::to_string(&exported[&TEST_CONTRACT]).unwrap(),
+            r#"{"balance":"0x0","nonce":"0x1","code":"0x60005460010160005500","storage":{"0x0000000000000000000000000000000000000000000000000000000000000000":"0x0000000000000000000000000000000000000000000000000000000000000006"}}"#
+        );
+        let exported: GenesisAlloc =
+            serde_json::from_str(&serde_json::to_string(&exported).unwrap()).unwrap();
+        assert_eq!(exported, db.genesis_alloc());
+    }
+
+    #[test]
+    fn test_bundle_state_genesis_alloc() {
+        let alloc: GenesisAlloc = serde_json::from_str(ALLOC).unwrap();
+        let mut cache = CacheState::new(true);
+        cache.insert_genesis_alloc(&alloc);
+        let mut state = State::builder()
+            .with_cached_prestate(cache)
+            .with_bundle_update()
+            .build();
+        increment(&mut state);
+        state.merge_transitions(BundleRetention::PlainState);
+
+        let exported = state.take_bundle().genesis_alloc();
+        assert_eq!(exported[&TEST_CONTRACT].code, alloc[&TEST_CONTRACT].code);
+        assert_eq!(exported[&TEST_CONTRACT].storage[&U256::ZERO], U256::from(6));
+        // the caller paid for the gas and bumped its nonce.
+        let caller = &exported[&TEST_CALLER];
+        assert_eq!(caller.nonce, 1);
+    }
+}
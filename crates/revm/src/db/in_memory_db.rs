@@ -38,6 +38,9 @@ pub struct CacheDB<ExtDB> {
     pub logs: Vec<Log>,
     /// All cached block hashes from the [DatabaseRef].
     pub block_hashes: HashMap<U256, B256>,
+    /// Poseidon hashes of the inserted contracts, indexed by their keccak code hash.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    pub poseidon_code_hashes: crate::primitives::PoseidonCodeHashCache,
     /// The underlying database ([DatabaseRef]) that is used to load data.
     ///
     /// Note: this is read-only, data is never written to this database.
@@ -60,6 +63,8 @@ impl<ExtDB> CacheDB<ExtDB> {
             contracts,
             logs: Vec::default(),
             block_hashes: HashMap::new(),
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            poseidon_code_hashes: Default::default(),
             db,
         }
     }
@@ -70,30 +75,12 @@ impl<ExtDB> CacheDB<ExtDB> {
     ///
     /// Note: This will not insert into the underlying external database.
     pub fn insert_contract(&mut self, account: &mut AccountInfo) {
-        if let Some(code) = &account.code {
-            if !code.is_empty() {
-                if account.code_hash == KECCAK_EMPTY {
-                    account.code_hash = code.hash_slow();
-                }
-                #[cfg(feature = "scroll")]
-                {
-                    account.code_size = code.len();
-                    #[cfg(feature = "scroll-poseidon-codehash")]
-                    {
-                        if account.poseidon_code_hash == crate::primitives::POSEIDON_EMPTY
-                            || account.poseidon_code_hash == B256::ZERO
-                        {
-                            account.poseidon_code_hash = code.poseidon_hash_slow();
-                        }
-                    }
-                }
-                self.contracts
-                    .entry(account.code_hash)
-                    .or_insert_with(|| code.clone());
-            }
-        }
-        if account.code_hash.is_zero() {
-            account.code_hash = KECCAK_EMPTY;
+        if let Some(code) = normalize_contract(
+            account,
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            &mut self.poseidon_code_hashes,
+        ) {
+            self.contracts.entry(account.code_hash).or_insert(code);
         }
     }
 
@@ -104,6 +91,44 @@ impl<ExtDB> CacheDB<ExtDB> {
     }
 }
 
+/// Fills the code hash and code info of the account from its code.
+///
+/// Returns the code to store by its code hash, `None` if the account has no code. Zero code
+/// hashes are replaced by [KECCAK_EMPTY].
+pub(crate) fn normalize_contract(
+    account: &mut AccountInfo,
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    poseidon_code_hashes: &mut crate::primitives::PoseidonCodeHashCache,
+) -> Option<Bytecode> {
+    let code = account
+        .code
+        .as_ref()
+        .filter(|code| !code.is_empty())
+        .cloned();
+    if let Some(code) = &code {
+        if account.code_hash == KECCAK_EMPTY {
+            account.code_hash = code.hash_slow();
+        }
+        #[cfg(feature = "scroll")]
+        {
+            account.code_size = code.len();
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            if account.poseidon_code_hash == crate::primitives::POSEIDON_EMPTY
+                || account.poseidon_code_hash == B256::ZERO
+            {
+                account.poseidon_code_hash =
+                    poseidon_code_hashes.get_or_compute(account.code_hash, code);
+            } else {
+                poseidon_code_hashes.insert(account.code_hash, account.poseidon_code_hash);
+            }
+        }
+    }
+    if account.code_hash.is_zero() {
+        account.code_hash = KECCAK_EMPTY;
+    }
+    code
+}
+
 impl<ExtDB: DatabaseRef> CacheDB<ExtDB> {
     /// Returns the account for the given address.
     ///
@@ -0,0 +1,348 @@
+//! [Database] wrapper that measures the calls to the database.
+
+use super::{AccountState, CacheDB, DatabaseCommit, State};
+use crate::primitives::{Account, AccountInfo, Address, Bytecode, HashMap, HashSet, B256, U256};
+use crate::Database;
+use std::{
+    time::{Duration, Instant},
+    vec::Vec,
+};
+
+/// Request made to a [Database].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DatabaseRequest {
+    /// [Database::basic] of the address.
+    Basic(Address),
+    /// [Database::code_by_hash] of the code hash.
+    CodeByHash(B256),
+    /// [Database::storage] of the address and index.
+    Storage(Address, U256),
+    /// [Database::block_hash] of the block number.
+    BlockHash(u64),
+}
+
+/// Database with an in-memory cache in front of the data source.
+pub trait CacheProbe {
+    /// Returns `true` if the request is answered without reaching the underlying database.
+    fn is_cached(&self, request: &DatabaseRequest) -> bool;
+}
+
+impl<T: CacheProbe> CacheProbe for &mut T {
+    fn is_cached(&self, request: &DatabaseRequest) -> bool {
+        (**self).is_cached(request)
+    }
+}
+
+impl<ExtDB> CacheProbe for CacheDB<ExtDB> {
+    fn is_cached(&self, request: &DatabaseRequest) -> bool {
+        match request {
+            DatabaseRequest::Basic(address) => self.accounts.contains_key(address),
+            DatabaseRequest::CodeByHash(code_hash) => self.contracts.contains_key(code_hash),
+            DatabaseRequest::Storage(address, index) => {
+                self.accounts.get(address).is_some_and(|account| {
+                    account.storage.contains_key(index)
+                        || matches!(
+                            account.account_state,
+                            AccountState::StorageCleared | AccountState::NotExisting
+                        )
+                })
+            }
+            DatabaseRequest::BlockHash(number) => {
+                self.block_hashes.contains_key(&U256::from(*number))
+            }
+        }
+    }
+}
+
+impl<DB> CacheProbe for State<DB> {
+    fn is_cached(&self, request: &DatabaseRequest) -> bool {
+        match request {
+            DatabaseRequest::Basic(address) => {
+                self.cache.accounts.contains_key(address)
+                    || (self.use_preloaded_bundle && self.bundle_state.state.contains_key(address))
+            }
+            DatabaseRequest::CodeByHash(code_hash) => {
+                self.cache.contracts.contains_key(code_hash)
+                    || (self.use_preloaded_bundle
+                        && self.bundle_state.contracts.contains_key(code_hash))
+            }
+            DatabaseRequest::Storage(address, index) => {
+                self.cache.accounts.get(address).is_some_and(|account| {
+                    account.status.is_storage_known()
+                        || account
+                            .account
+                            .as_ref()
+                            .is_some_and(|account| account.storage.contains_key(index))
+                })
+            }
+            DatabaseRequest::BlockHash(number) => self.block_hashes.contains_key(number),
+        }
+    }
+}
+
+/// Statistics of the calls to one [Database] method.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MethodStats {
+    /// Number of calls.
+    pub calls: u64,
+    /// Number of calls answered by the cache, only counted if the database is a [CacheProbe].
+    pub hits: u64,
+    /// Number of calls that reached the underlying database, only counted if the database is
+    /// a [CacheProbe].
+    pub misses: u64,
+    /// Total time spent in the calls.
+    pub latency: Duration,
+}
+
+/// Statistics of the calls to a [Database].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DatabaseStats {
+    /// Calls to [Database::basic].
+    pub basic: MethodStats,
+    /// Calls to [Database::code_by_hash].
+    pub code_by_hash: MethodStats,
+    /// Calls to [Database::storage].
+    pub storage: MethodStats,
+    /// Calls to [Database::block_hash].
+    pub block_hash: MethodStats,
+    /// Distinct accounts that were requested.
+    pub accounts: HashSet<Address>,
+    /// Distinct storage slots that were requested.
+    pub slots: HashSet<(Address, U256)>,
+}
+
+impl DatabaseStats {
+    /// Returns the total number of calls.
+    pub fn calls(&self) -> u64 {
+        self.methods().map(|(_, stats)| stats.calls).sum()
+    }
+
+    /// Returns the total time spent in the calls.
+    pub fn latency(&self) -> Duration {
+        self.methods().map(|(_, stats)| stats.latency).sum()
+    }
+
+    /// Returns the statistics of each method, with the method name.
+    pub fn methods(&self) -> impl Iterator<Item = (&'static str, &MethodStats)> {
+        [
+            ("basic", &self.basic),
+            ("code_by_hash", &self.code_by_hash),
+            ("storage", &self.storage),
+            ("block_hash", &self.block_hash),
+        ]
+        .into_iter()
+    }
+
+    fn record(&mut self, request: &DatabaseRequest, cached: Option<bool>, latency: Duration) {
+        let stats = match request {
+            DatabaseRequest::Basic(address) => {
+                self.accounts.insert(*address);
+                &mut self.basic
+            }
+            DatabaseRequest::CodeByHash(_) => &mut self.code_by_hash,
+            DatabaseRequest::Storage(address, index) => {
+                self.slots.insert((*address, *index));
+                &mut self.storage
+            }
+            DatabaseRequest::BlockHash(_) => &mut self.block_hash,
+        };
+        stats.calls += 1;
+        match cached {
+            Some(true) => stats.hits += 1,
+            Some(false) => stats.misses += 1,
+            None => {}
+        }
+        stats.latency += latency;
+    }
+
+    /// Records the statistics through the `metrics` facade.
+    ///
+    /// Counters are incremented by the values of the statistics, so each call should be done
+    /// with new statistics, for example the ones returned by [MetricsDB::take_stats] after each
+    /// block.
+    #[cfg(feature = "metrics")]
+    pub fn record_metrics(&self) {
+        for (method, stats) in self.methods() {
+            metrics::counter!("revm_db_calls_total", "method" => method).increment(stats.calls);
+            metrics::counter!("revm_db_cache_hits_total", "method" => method).increment(stats.hits);
+            metrics::counter!("revm_db_cache_misses_total", "method" => method)
+                .increment(stats.misses);
+            metrics::counter!("revm_db_latency_nanoseconds_total", "method" => method)
+                .increment(stats.latency.as_nanos() as u64);
+        }
+        metrics::histogram!("revm_db_accounts").record(self.accounts.len() as f64);
+        metrics::histogram!("revm_db_slots").record(self.slots.len() as f64);
+    }
+}
+
+/// [Database] wrapper that counts the calls, cache hits and misses and the time spent in each
+/// method.
+///
+/// The executor marks the transaction being executed with [MetricsDB::set_transaction], the
+/// calls made during it are also recorded in the statistics of the transaction.
+#[derive(Debug)]
+pub struct MetricsDB<DB> {
+    /// The wrapped database.
+    pub db: DB,
+    stats: DatabaseStats,
+    transactions: Vec<(B256, DatabaseStats)>,
+    /// Whether the calls are recorded in the last transaction.
+    in_transaction: bool,
+    is_cached: Option<fn(&DB, &DatabaseRequest) -> bool>,
+}
+
+impl<DB> MetricsDB<DB> {
+    /// Wraps the database, cache hits and misses are not counted.
+    pub fn new(db: DB) -> Self {
+        Self {
+            db,
+            stats: DatabaseStats::default(),
+            transactions: Vec::new(),
+            in_transaction: false,
+            is_cached: None,
+        }
+    }
+
+    /// Wraps the caching database, and counts cache hits and misses.
+    pub fn with_cache_stats(db: DB) -> Self
+    where
+        DB: CacheProbe,
+    {
+        Self {
+            is_cached: Some(|db, request| db.is_cached(request)),
+            ..Self::new(db)
+        }
+    }
+
+    /// Sets the transaction the following calls belong to, `None` if they do not belong to a
+    /// transaction.
+    ///
+    /// Each call with a hash starts new statistics for the transaction.
+    pub fn set_transaction(&mut self, tx_hash: Option<B256>) {
+        self.in_transaction = tx_hash.is_some();
+        if let Some(tx_hash) = tx_hash {
+            self.transactions.push((tx_hash, DatabaseStats::default()));
+        }
+    }
+
+    /// Returns the statistics of all calls.
+    pub fn stats(&self) -> &DatabaseStats {
+        &self.stats
+    }
+
+    /// Returns the statistics of each transaction, in the order they were set.
+    pub fn transactions(&self) -> &[(B256, DatabaseStats)] {
+        &self.transactions
+    }
+
+    /// Returns the statistics of all calls and of each transaction, and resets them.
+    pub fn take_stats(&mut self) -> (DatabaseStats, Vec<(B256, DatabaseStats)>) {
+        self.in_transaction = false;
+        (
+            core::mem::take(&mut self.stats),
+            core::mem::take(&mut self.transactions),
+        )
+    }
+
+    /// Returns the wrapped database.
+    pub fn into_inner(self) -> DB {
+        self.db
+    }
+
+    fn measure<T>(&mut self, request: DatabaseRequest, f: impl FnOnce(&mut DB) -> T) -> T {
+        let cached = self
+            .is_cached
+            .map(|is_cached| is_cached(&self.db, &request));
+        let start = Instant::now();
+        let output = f(&mut self.db);
+        let latency = start.elapsed();
+
+        self.stats.record(&request, cached, latency);
+        if self.in_transaction {
+            if let Some((_, stats)) = self.transactions.last_mut() {
+                stats.record(&request, cached, latency);
+            }
+        }
+        output
+    }
+}
+
+impl<DB: Database> Database for MetricsDB<DB> {
+    type Error = DB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.measure(DatabaseRequest::Basic(address), |db| db.basic(address))
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.measure(DatabaseRequest::CodeByHash(code_hash), |db| {
+            db.code_by_hash(code_hash)
+        })
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.measure(DatabaseRequest::Storage(address, index), |db| {
+            db.storage(address, index)
+        })
+    }
+
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        self.measure(DatabaseRequest::BlockHash(number), |db| {
+            db.block_hash(number)
+        })
+    }
+}
+
+impl<DB: DatabaseCommit> DatabaseCommit for MetricsDB<DB> {
+    fn commit(&mut self, changes: HashMap<Address, Account>) {
+        self.db.commit(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{EmptyDB, InMemoryDB},
+        primitives::Bytes,
+        test_utils::{test_call_commit, TEST_CALLER, TEST_CONTRACT},
+    };
+
+    #[test]
+    fn test_metrics_db() {
+        let mut db = InMemoryDB::new(EmptyDB::default());
+        // SLOAD(1), SLOAD(1), SLOAD(2)
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                0x60, 0x01, 0x54, 0x60, 0x01, 0x54, 0x60, 0x02, 0x54, 0x00,
+            ]))),
+        );
+        let mut db = MetricsDB::with_cache_stats(db);
+
+        for tx_hash in [B256::with_last_byte(1), B256::with_last_byte(2)] {
+            db.set_transaction(Some(tx_hash));
+            assert!(test_call_commit(&mut db).unwrap().is_success());
+        }
+        db.set_transaction(None);
+
+        let [(_, first), (_, second)] = db.transactions() else {
+            panic!("expected two transactions");
+        };
+        // slots are loaded in the first transaction, the second is answered by the cache.
+        assert!(first.storage.misses >= 2);
+        assert_eq!(second.storage.misses, 0);
+        assert_eq!(second.storage.hits, second.storage.calls);
+        assert!(first.accounts.contains(&TEST_CALLER) && first.accounts.contains(&TEST_CONTRACT));
+        assert!(first.slots.contains(&(TEST_CONTRACT, U256::from(1))));
+        assert!(first.slots.contains(&(TEST_CONTRACT, U256::from(2))));
+
+        let stats = db.stats();
+        assert_eq!(stats.calls(), first.calls() + second.calls());
+        assert_eq!(
+            stats.storage.hits + stats.storage.misses,
+            stats.storage.calls
+        );
+        assert_eq!(stats.slots, first.slots);
+    }
+}
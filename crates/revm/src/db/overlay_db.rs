@@ -0,0 +1,520 @@
+//! State and block overrides, same as the ones of the `eth_call` RPC method.
+
+use crate::primitives::{
+    Account, AccountInfo, Address, BlockEnv, Bytecode, Bytes, HashMap, B256, U256,
+};
+use crate::{Database, DatabaseCommit, DatabaseRef};
+use core::fmt;
+
+/// Account overrides indexed by address.
+pub type StateOverride = HashMap<Address, AccountOverride>;
+
+/// Overrides of a single account.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default))]
+pub struct AccountOverride {
+    /// Replaces the balance.
+    pub balance: Option<U256>,
+    /// Replaces the nonce.
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex_opt_u64"))]
+    pub nonce: Option<u64>,
+    /// Replaces the code.
+    pub code: Option<Bytes>,
+    /// Replaces the whole storage, slots that are not set are zero.
+    pub state: Option<HashMap<B256, B256>>,
+    /// Replaces the given storage slots, other slots are left unchanged.
+    pub state_diff: Option<HashMap<B256, B256>>,
+}
+
+/// Error returned by [OverlayDB::new] for invalid overrides.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateOverrideError {
+    /// Account override has both `state` and `state_diff` set.
+    StateAndStateDiff(Address),
+}
+
+impl fmt::Display for StateOverrideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StateAndStateDiff(address) => {
+                write!(f, "account {address} has both 'state' and 'stateDiff'")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StateOverrideError {}
+
+/// Overrides of the block environment.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default))]
+pub struct BlockOverrides {
+    /// Replaces the block number.
+    pub number: Option<U256>,
+    /// Replaces the block timestamp.
+    pub time: Option<U256>,
+    /// Replaces the block gas limit.
+    pub gas_limit: Option<U256>,
+    /// Replaces the coinbase.
+    #[cfg_attr(feature = "serde", serde(rename = "feeRecipient", alias = "coinbase"))]
+    pub coinbase: Option<Address>,
+    /// Replaces the base fee.
+    #[cfg_attr(feature = "serde", serde(rename = "baseFeePerGas"))]
+    pub basefee: Option<U256>,
+    /// Replaces the prevrandao.
+    #[cfg_attr(feature = "serde", serde(rename = "prevRandao"))]
+    pub prevrandao: Option<B256>,
+}
+
+impl BlockOverrides {
+    /// Applies the overrides to the block environment.
+    pub fn apply(&self, block: &mut BlockEnv) {
+        if let Some(number) = self.number {
+            block.number = number;
+        }
+        if let Some(time) = self.time {
+            block.timestamp = time;
+        }
+        if let Some(gas_limit) = self.gas_limit {
+            block.gas_limit = gas_limit;
+        }
+        if let Some(coinbase) = self.coinbase {
+            block.coinbase = coinbase;
+        }
+        if let Some(basefee) = self.basefee {
+            block.basefee = basefee;
+        }
+        if let Some(prevrandao) = self.prevrandao {
+            block.prevrandao = Some(prevrandao);
+        }
+    }
+}
+
+/// Override of a single account, with the code already hashed.
+#[derive(Clone, Debug, Default)]
+struct OverlayAccount {
+    balance: Option<U256>,
+    nonce: Option<u64>,
+    /// Overridden code info, only the code fields are used.
+    code: Option<AccountInfo>,
+    /// Overridden storage slots.
+    storage: HashMap<U256, U256>,
+    /// If `true`, slots that are not overridden are zero.
+    storage_cleared: bool,
+}
+
+impl OverlayAccount {
+    fn new(address: Address, account: &AccountOverride) -> Result<Self, StateOverrideError> {
+        let (storage, storage_cleared) = match (&account.state, &account.state_diff) {
+            (Some(_), Some(_)) => return Err(StateOverrideError::StateAndStateDiff(address)),
+            (Some(state), None) => (state, true),
+            (None, Some(state_diff)) => (state_diff, false),
+            (None, None) => (&HashMap::new(), false),
+        };
+        let code = account
+            .code
+            .clone()
+            .map(|code| AccountInfo::from_bytecode(Bytecode::new_raw(code)));
+        Ok(Self {
+            balance: account.balance,
+            nonce: account.nonce,
+            code,
+            storage: storage
+                .iter()
+                .map(|(slot, value)| ((*slot).into(), (*value).into()))
+                .collect(),
+            storage_cleared,
+        })
+    }
+
+    /// Applies the overrides to the account info, non existing accounts are created.
+    fn apply(&self, info: Option<AccountInfo>) -> AccountInfo {
+        let mut info = info.unwrap_or_default();
+        if let Some(balance) = self.balance {
+            info.balance = balance;
+        }
+        if let Some(nonce) = self.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code) = &self.code {
+            info.code_hash = code.code_hash;
+            info.code.clone_from(&code.code);
+            #[cfg(feature = "scroll")]
+            {
+                info.code_size = code.code_size;
+            }
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            {
+                info.poseidon_code_hash = code.poseidon_code_hash;
+            }
+        }
+        info
+    }
+
+    /// Drops the overrides that are replaced by the committed account.
+    fn commit(&mut self, account: &Account) {
+        self.balance = None;
+        self.nonce = None;
+        self.code = None;
+        if account.is_selfdestructed() || account.is_created() {
+            // storage is cleared in the underlying database as well.
+            self.storage.clear();
+            self.storage_cleared = false;
+            return;
+        }
+        for (index, slot) in account.changed_storage_slots() {
+            if self.storage_cleared {
+                self.storage.insert(*index, slot.present_value);
+            } else {
+                self.storage.remove(index);
+            }
+        }
+    }
+
+    /// Returns the overridden storage value, `None` if the slot is read from the database.
+    fn storage(&self, index: U256) -> Option<U256> {
+        match self.storage.get(&index) {
+            Some(value) => Some(*value),
+            None if self.storage_cleared => Some(U256::ZERO),
+            None => None,
+        }
+    }
+}
+
+/// A [Database] wrapper that applies a [StateOverride] on top of the underlying database.
+///
+/// Overrides are applied when the state is read and the underlying database is never modified.
+/// Accounts with a `state` override do not read any storage slot from the underlying database.
+#[derive(Clone, Debug)]
+pub struct OverlayDB<DB> {
+    /// Account overrides.
+    accounts: HashMap<Address, OverlayAccount>,
+    /// Overridden code indexed by its hash.
+    contracts: HashMap<B256, Bytecode>,
+    /// The underlying database.
+    pub db: DB,
+}
+
+impl<DB> OverlayDB<DB> {
+    /// Creates a new overlay of the database with the given overrides.
+    pub fn new(db: DB, state_override: &StateOverride) -> Result<Self, StateOverrideError> {
+        let mut accounts = HashMap::with_capacity(state_override.len());
+        let mut contracts = HashMap::new();
+        for (address, account) in state_override {
+            let account = OverlayAccount::new(*address, account)?;
+            if let Some(AccountInfo {
+                code_hash,
+                code: Some(code),
+                ..
+            }) = &account.code
+            {
+                contracts.insert(*code_hash, code.clone());
+            }
+            accounts.insert(*address, account);
+        }
+        Ok(Self {
+            accounts,
+            contracts,
+            db,
+        })
+    }
+
+    /// Consumes the overlay and returns the underlying database.
+    pub fn into_inner(self) -> DB {
+        self.db
+    }
+}
+
+impl<DB: Database> Database for OverlayDB<DB> {
+    type Error = DB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let info = self.db.basic(address)?;
+        Ok(match self.accounts.get(&address) {
+            Some(account) => Some(account.apply(info)),
+            None => info,
+        })
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        match self.contracts.get(&code_hash) {
+            Some(code) => Ok(code.clone()),
+            None => self.db.code_by_hash(code_hash),
+        }
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        match self
+            .accounts
+            .get(&address)
+            .and_then(|account| account.storage(index))
+        {
+            Some(value) => Ok(value),
+            None => self.db.storage(address, index),
+        }
+    }
+
+    #[inline]
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        self.db.block_hash(number)
+    }
+}
+
+impl<DB: DatabaseRef> DatabaseRef for OverlayDB<DB> {
+    type Error = DB::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let info = self.db.basic_ref(address)?;
+        Ok(match self.accounts.get(&address) {
+            Some(account) => Some(account.apply(info)),
+            None => info,
+        })
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        match self.contracts.get(&code_hash) {
+            Some(code) => Ok(code.clone()),
+            None => self.db.code_by_hash_ref(code_hash),
+        }
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        match self
+            .accounts
+            .get(&address)
+            .and_then(|account| account.storage(index))
+        {
+            Some(value) => Ok(value),
+            None => self.db.storage_ref(address, index),
+        }
+    }
+
+    #[inline]
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.db.block_hash_ref(number)
+    }
+}
+
+impl<DB: DatabaseCommit> DatabaseCommit for OverlayDB<DB> {
+    /// Commits the changes to the underlying database.
+    ///
+    /// Overrides of the committed accounts are dropped so that the committed values are read
+    /// afterwards. Slots of accounts with a `state` override that were not written stay zero.
+    fn commit(&mut self, changes: HashMap<Address, Account>) {
+        for (address, account) in &changes {
+            if !account.is_touched() {
+                continue;
+            }
+            if let Some(overlay) = self.accounts.get_mut(address) {
+                overlay.commit(account);
+            }
+        }
+        self.db.commit(changes)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_hex_opt_u64 {
+    use crate::primitives::alloy_primitives::U64;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        value: &Option<u64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.map(U64::from).serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        Option::<U64>::deserialize(deserializer).map(|value| value.map(|value| value.to()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{CacheDB, EmptyDB};
+    use crate::primitives::{address, bytes, TxKind, KECCAK_EMPTY};
+    use crate::test_utils::{test_evm_builder, TEST_CALLER, TEST_CONTRACT};
+
+    fn cache_db() -> CacheDB<EmptyDB> {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            Address::with_last_byte(1),
+            AccountInfo {
+                balance: U256::from(10),
+                nonce: 1,
+                ..Default::default()
+            },
+        );
+        for slot in 1..3 {
+            db.insert_account_storage(Address::with_last_byte(1), U256::from(slot), U256::from(1))
+                .unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn test_account_override() {
+        let code = bytes!("6001600155");
+        let state_override = StateOverride::from_iter([
+            (
+                Address::with_last_byte(1),
+                AccountOverride {
+                    balance: Some(U256::from(20)),
+                    code: Some(code.clone()),
+                    ..Default::default()
+                },
+            ),
+            (
+                Address::with_last_byte(2),
+                AccountOverride {
+                    nonce: Some(5),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let mut db = OverlayDB::new(cache_db(), &state_override).unwrap();
+
+        let info = db.basic(Address::with_last_byte(1)).unwrap().unwrap();
+        let bytecode = Bytecode::new_raw(code);
+        assert_eq!(info.balance, U256::from(20));
+        assert_eq!(info.nonce, 1);
+        assert_eq!(info.code_hash, bytecode.hash_slow());
+        #[cfg(feature = "scroll")]
+        assert_eq!(info.code_size, bytecode.len());
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        assert_eq!(info.poseidon_code_hash, bytecode.poseidon_hash_slow());
+        assert_eq!(db.code_by_hash(info.code_hash).unwrap(), bytecode);
+
+        // override creates the account.
+        let info = db.basic(Address::with_last_byte(2)).unwrap().unwrap();
+        assert_eq!(info.nonce, 5);
+        assert_eq!(info.code_hash, KECCAK_EMPTY);
+        assert_eq!(db.basic(Address::with_last_byte(3)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_storage_override() {
+        let address = Address::with_last_byte(1);
+        let slots = HashMap::from_iter([(B256::from(U256::from(1)), B256::from(U256::from(2)))]);
+        let override_with = |account: AccountOverride| {
+            OverlayDB::new(cache_db(), &StateOverride::from_iter([(address, account)])).unwrap()
+        };
+
+        let mut db = override_with(AccountOverride {
+            state_diff: Some(slots.clone()),
+            ..Default::default()
+        });
+        assert_eq!(db.storage(address, U256::from(1)).unwrap(), U256::from(2));
+        assert_eq!(db.storage(address, U256::from(2)).unwrap(), U256::from(1));
+
+        // full state replacement clears the other slots.
+        let db = override_with(AccountOverride {
+            state: Some(slots.clone()),
+            ..Default::default()
+        });
+        assert_eq!(
+            db.storage_ref(address, U256::from(1)).unwrap(),
+            U256::from(2)
+        );
+        assert_eq!(db.storage_ref(address, U256::from(2)).unwrap(), U256::ZERO);
+
+        let err = OverlayDB::new(
+            cache_db(),
+            &StateOverride::from_iter([(
+                address,
+                AccountOverride {
+                    state: Some(slots.clone()),
+                    state_diff: Some(slots),
+                    ..Default::default()
+                },
+            )]),
+        )
+        .unwrap_err();
+        assert_eq!(err, StateOverrideError::StateAndStateDiff(address));
+    }
+
+    #[test]
+    fn test_commit_drops_overrides() {
+        let slot = |value: u64| B256::from(U256::from(value));
+        let state_override = StateOverride::from_iter([
+            (
+                TEST_CALLER,
+                AccountOverride {
+                    nonce: Some(5),
+                    ..Default::default()
+                },
+            ),
+            (
+                TEST_CONTRACT,
+                AccountOverride {
+                    // SSTORE(1, 7)
+                    code: Some(bytes!("6007600155")),
+                    state: Some(HashMap::from_iter([(slot(1), slot(2)), (slot(2), slot(3))])),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let mut cache_db = CacheDB::new(EmptyDB::default());
+        cache_db
+            .insert_account_storage(TEST_CONTRACT, U256::from(3), U256::from(4))
+            .unwrap();
+        let mut db = OverlayDB::new(cache_db, &state_override).unwrap();
+
+        test_evm_builder(&mut db, TxKind::Call(TEST_CONTRACT))
+            .build()
+            .transact_commit()
+            .unwrap();
+
+        assert_eq!(db.basic(TEST_CALLER).unwrap().unwrap().nonce, 6);
+        let storage =
+            |db: &mut OverlayDB<_>, index: u64| db.storage(TEST_CONTRACT, U256::from(index));
+        assert_eq!(storage(&mut db, 1).unwrap(), U256::from(7));
+        assert_eq!(storage(&mut db, 2).unwrap(), U256::from(3));
+        // other slots are still cleared by the `state` override.
+        assert_eq!(storage(&mut db, 3).unwrap(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_block_overrides() {
+        let mut block = BlockEnv::default();
+        let coinbase = address!("1000000000000000000000000000000000000000");
+        BlockOverrides {
+            number: Some(U256::from(100)),
+            coinbase: Some(coinbase),
+            ..Default::default()
+        }
+        .apply(&mut block);
+        assert_eq!(block.number, U256::from(100));
+        assert_eq!(block.coinbase, coinbase);
+        assert_eq!(block.timestamp, BlockEnv::default().timestamp);
+    }
+
+    #[cfg(feature = "serde-json")]
+    #[test]
+    fn test_deserialize_state_override() {
+        let state_override: StateOverride = serde_json::from_str(
+            r#"{
+                "0x0000000000000000000000000000000000000001": {
+                    "balance": "0x14",
+                    "nonce": "0x5",
+                    "stateDiff": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000001": "0x0000000000000000000000000000000000000000000000000000000000000002"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let account = &state_override[&Address::with_last_byte(1)];
+        assert_eq!(account.balance, Some(U256::from(20)));
+        assert_eq!(account.nonce, Some(5));
+        assert_eq!(account.state, None);
+        assert_eq!(account.state_diff.as_ref().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,345 @@
+//! Databases that record the responses of another database and replay them offline.
+
+use crate::primitives::{AccessListItem, AccountInfo, Address, Bytecode, B256, U256};
+use crate::{Database, DatabaseRef};
+use core::{cell::RefCell, fmt};
+use std::{collections::BTreeMap, vec::Vec};
+
+/// Responses of a database, recorded by [RecordingDB] and served by [ReplayDB].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct DbFixture {
+    /// Account info of each address, `None` if the account does not exist.
+    pub accounts: BTreeMap<Address, Option<AccountInfo>>,
+    /// Bytecode of each code hash.
+    pub contracts: BTreeMap<B256, Bytecode>,
+    /// Storage slots of each address.
+    pub storage: BTreeMap<Address, BTreeMap<U256, U256>>,
+    /// Block hash of each block number.
+    pub block_hashes: BTreeMap<u64, B256>,
+}
+
+impl DbFixture {
+    /// Returns the recorded accounts and storage slots as an access list, used to prefetch
+    /// the state of a later run.
+    pub fn access_list(&self) -> Vec<AccessListItem> {
+        self.accounts
+            .keys()
+            .chain(self.storage.keys())
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .map(|address| AccessListItem {
+                address: *address,
+                storage_keys: self
+                    .storage
+                    .get(address)
+                    .map(|storage| storage.keys().map(|index| B256::from(*index)).collect())
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(feature = "std", feature = "serde-json"))]
+impl DbFixture {
+    /// Reads a fixture from a JSON file.
+    pub fn load_json(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Writes the fixture to a JSON file.
+    pub fn save_json(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        Ok(serde_json::to_writer_pretty(file, self)?)
+    }
+}
+
+/// A [Database] wrapper that records every response of the underlying database in a
+/// [DbFixture].
+///
+/// Errors of the underlying database are not recorded.
+#[derive(Debug)]
+pub struct RecordingDB<DB> {
+    /// Recorded responses.
+    fixture: RefCell<DbFixture>,
+    /// The underlying database.
+    pub db: DB,
+}
+
+impl<DB> RecordingDB<DB> {
+    /// Wraps the database with an empty fixture.
+    pub fn new(db: DB) -> Self {
+        Self {
+            fixture: RefCell::default(),
+            db,
+        }
+    }
+
+    /// Returns a copy of the recorded responses.
+    pub fn fixture(&self) -> DbFixture {
+        self.fixture.borrow().clone()
+    }
+
+    /// Consumes the wrapper and returns the recorded responses.
+    pub fn into_fixture(self) -> DbFixture {
+        self.fixture.into_inner()
+    }
+
+    fn record_basic(&self, address: Address, info: &Option<AccountInfo>) {
+        self.fixture
+            .borrow_mut()
+            .accounts
+            .insert(address, info.clone());
+    }
+
+    fn record_code(&self, code_hash: B256, code: &Bytecode) {
+        self.fixture
+            .borrow_mut()
+            .contracts
+            .insert(code_hash, code.clone());
+    }
+
+    fn record_storage(&self, address: Address, index: U256, value: U256) {
+        self.fixture
+            .borrow_mut()
+            .storage
+            .entry(address)
+            .or_default()
+            .insert(index, value);
+    }
+
+    fn record_block_hash(&self, number: u64, hash: B256) {
+        self.fixture.borrow_mut().block_hashes.insert(number, hash);
+    }
+}
+
+impl<DB: Database> Database for RecordingDB<DB> {
+    type Error = DB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let info = self.db.basic(address)?;
+        self.record_basic(address, &info);
+        Ok(info)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        let code = self.db.code_by_hash(code_hash)?;
+        self.record_code(code_hash, &code);
+        Ok(code)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let value = self.db.storage(address, index)?;
+        self.record_storage(address, index, value);
+        Ok(value)
+    }
+
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        let hash = self.db.block_hash(number)?;
+        self.record_block_hash(number, hash);
+        Ok(hash)
+    }
+}
+
+impl<DB: DatabaseRef> DatabaseRef for RecordingDB<DB> {
+    type Error = DB::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let info = self.db.basic_ref(address)?;
+        self.record_basic(address, &info);
+        Ok(info)
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        let code = self.db.code_by_hash_ref(code_hash)?;
+        self.record_code(code_hash, &code);
+        Ok(code)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let value = self.db.storage_ref(address, index)?;
+        self.record_storage(address, index, value);
+        Ok(value)
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        let hash = self.db.block_hash_ref(number)?;
+        self.record_block_hash(number, hash);
+        Ok(hash)
+    }
+}
+
+/// Error returned by [ReplayDB] when a response was not recorded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// Account was not recorded.
+    MissingAccount(Address),
+    /// Code was not recorded.
+    MissingCode(B256),
+    /// Storage slot was not recorded.
+    MissingStorage { address: Address, index: U256 },
+    /// Block hash was not recorded.
+    MissingBlockHash(u64),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingAccount(address) => write!(f, "account {address} was not recorded"),
+            Self::MissingCode(code_hash) => write!(f, "code {code_hash} was not recorded"),
+            Self::MissingStorage { address, index } => {
+                write!(f, "storage slot {index} of {address} was not recorded")
+            }
+            Self::MissingBlockHash(number) => {
+                write!(f, "hash of block {number} was not recorded")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReplayError {}
+
+/// A [Database] that serves the responses of a [DbFixture].
+///
+/// Every request that was not recorded returns a [ReplayError].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplayDB {
+    /// Recorded responses.
+    pub fixture: DbFixture,
+}
+
+impl ReplayDB {
+    /// Creates a database that answers from the recorded fixture.
+    pub fn new(fixture: DbFixture) -> Self {
+        Self { fixture }
+    }
+}
+
+impl Database for ReplayDB {
+    type Error = ReplayError;
+
+    #[inline]
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.basic_ref(address)
+    }
+
+    #[inline]
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.code_by_hash_ref(code_hash)
+    }
+
+    #[inline]
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.storage_ref(address, index)
+    }
+
+    #[inline]
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        self.block_hash_ref(number)
+    }
+}
+
+impl DatabaseRef for ReplayDB {
+    type Error = ReplayError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.fixture
+            .accounts
+            .get(&address)
+            .cloned()
+            .ok_or(ReplayError::MissingAccount(address))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.fixture
+            .contracts
+            .get(&code_hash)
+            .cloned()
+            .ok_or(ReplayError::MissingCode(code_hash))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.fixture
+            .storage
+            .get(&address)
+            .and_then(|storage| storage.get(&index))
+            .copied()
+            .ok_or(ReplayError::MissingStorage { address, index })
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.fixture
+            .block_hashes
+            .get(&number)
+            .copied()
+            .ok_or(ReplayError::MissingBlockHash(number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        primitives::{Bytes, TxKind},
+        test_utils::{test_evm_builder, TEST_CONTRACT},
+    };
+
+    fn transact<DB: Database>(db: DB) -> Result<crate::primitives::ExecutionResult, DB::Error> {
+        let mut evm = test_evm_builder(db, TxKind::Call(TEST_CONTRACT))
+            .modify_block_env(|block| block.number = U256::from(10))
+            .build();
+        evm.transact()
+            .map(|result| result.result)
+            .map_err(|err| match err {
+                crate::primitives::EVMError::Database(err) => err,
+                _ => panic!("unexpected error"),
+            })
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut db = CacheDB::new(EmptyDB::default());
+        // SLOAD(1), BLOCKHASH(9)
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                0x60, 0x01, 0x54, 0x60, 0x09, 0x40, 0x00,
+            ]))),
+        );
+        db.insert_account_storage(TEST_CONTRACT, U256::from(1), U256::from(2))
+            .unwrap();
+
+        let mut recording = RecordingDB::new(db);
+        let recorded = transact(&mut recording).unwrap();
+        let fixture = recording.into_fixture();
+        assert_eq!(
+            fixture.storage[&TEST_CONTRACT][&U256::from(1)],
+            U256::from(2)
+        );
+        // scroll derives block hashes without querying the database.
+        #[cfg(not(feature = "scroll"))]
+        assert!(fixture.block_hashes.contains_key(&9));
+
+        #[cfg(feature = "serde-json")]
+        let fixture: DbFixture =
+            serde_json::from_str(&serde_json::to_string(&fixture).unwrap()).unwrap();
+        let replayed = transact(ReplayDB::new(fixture.clone())).unwrap();
+        assert_eq!(replayed, recorded);
+
+        // missing slot is reported.
+        let mut fixture = fixture;
+        fixture.storage.clear();
+        assert_eq!(
+            transact(ReplayDB::new(fixture)).unwrap_err(),
+            ReplayError::MissingStorage {
+                address: TEST_CONTRACT,
+                index: U256::from(1)
+            }
+        );
+    }
+}
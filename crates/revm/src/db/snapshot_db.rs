@@ -0,0 +1,559 @@
+//! Copy-on-write database with cheap snapshots, used to execute many branches from the same
+//! state.
+
+use super::{
+    in_memory_db::normalize_contract, AccountState, DatabaseCommit, DatabaseRef, DbAccount,
+};
+use crate::primitives::{Account, AccountInfo, Address, Bytecode, HashMap, HashSet, B256, U256};
+use crate::Database;
+use core::{fmt, mem};
+use std::{sync::Arc, vec::Vec};
+
+/// Changes written to an account in a [Layer], as opposed to values read from the layers below.
+#[derive(Debug, Default)]
+struct AccountWrites {
+    /// Whether the account info was written.
+    info: bool,
+    /// Whether the storage was cleared, in which case the whole account of the layer is written.
+    cleared: bool,
+    /// Written storage slots.
+    storage: HashSet<U256>,
+}
+
+impl AccountWrites {
+    fn extend(&mut self, other: &AccountWrites) {
+        self.info |= other.info;
+        self.cleared |= other.cleared;
+        self.storage.extend(&other.storage);
+    }
+}
+
+/// Changes of a branch on top of the frozen layers of its parents.
+#[derive(Debug, Default)]
+struct Layer {
+    /// Accounts changed or loaded in this layer.
+    ///
+    /// Storage only holds the slots changed or loaded in this layer, other slots are read from
+    /// the layers below unless the storage was cleared, as in [super::CacheDB].
+    accounts: HashMap<Address, DbAccount>,
+    /// Contracts inserted or loaded in this layer.
+    contracts: HashMap<B256, Bytecode>,
+    /// Block hashes loaded in this layer.
+    block_hashes: HashMap<u64, B256>,
+    /// Accounts and slots of `accounts` that were written in this layer, the others were read.
+    writes: HashMap<Address, AccountWrites>,
+    /// The frozen layer below, `None` for the bottom layer.
+    parent: Option<Arc<Layer>>,
+}
+
+impl Layer {
+    fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.contracts.is_empty() && self.block_hashes.is_empty()
+    }
+
+    /// Iterates over this layer and the layers below it.
+    fn iter(&self) -> impl Iterator<Item = &Layer> {
+        core::iter::successors(Some(self), |layer| layer.parent.as_deref())
+    }
+
+    /// Returns the account from the topmost layer that has it.
+    fn account(&self, address: &Address) -> Option<&DbAccount> {
+        self.iter().find_map(|layer| layer.accounts.get(address))
+    }
+
+    /// Returns the storage slot from the topmost layer that knows it, `None` if it has to be
+    /// read from the database.
+    fn storage(&self, address: &Address, index: &U256) -> Option<U256> {
+        for account in self.iter().filter_map(|layer| layer.accounts.get(address)) {
+            if let Some(value) = account.storage.get(index) {
+                return Some(*value);
+            }
+            if matches!(
+                account.account_state,
+                AccountState::StorageCleared | AccountState::NotExisting
+            ) {
+                return Some(U256::ZERO);
+            }
+        }
+        None
+    }
+
+    fn contract(&self, code_hash: &B256) -> Option<&Bytecode> {
+        self.iter().find_map(|layer| layer.contracts.get(code_hash))
+    }
+
+    fn block_hash(&self, number: u64) -> Option<B256> {
+        self.iter()
+            .find_map(|layer| layer.block_hashes.get(&number).copied())
+    }
+
+    /// Returns the account of this layer, copying its info from the layers below if needed.
+    fn account_mut(&mut self, address: Address) -> &mut DbAccount {
+        if !self.accounts.contains_key(&address) {
+            let account = self
+                .parent
+                .as_deref()
+                .and_then(|parent| parent.account(&address));
+            self.accounts.insert(address, Self::copy_account(account));
+        }
+        self.accounts.get_mut(&address).unwrap()
+    }
+
+    /// Copies an account of the layers below into a new layer.
+    ///
+    /// Storage is not copied, unknown slots keep being read from the layers below. Accounts with
+    /// cleared storage are copied with their slots, as the layers below them are not read.
+    fn copy_account(account: Option<&DbAccount>) -> DbAccount {
+        account
+            .map(|account| DbAccount {
+                info: account.info.clone(),
+                account_state: account.account_state.clone(),
+                storage: if matches!(
+                    account.account_state,
+                    AccountState::StorageCleared | AccountState::NotExisting
+                ) {
+                    account.storage.clone()
+                } else {
+                    Default::default()
+                },
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the account of this layer to write to, see [Layer::account_mut].
+    fn write(&mut self, address: Address) -> (&mut DbAccount, &mut AccountWrites) {
+        self.account_mut(address);
+        (
+            self.accounts.get_mut(&address).unwrap(),
+            self.writes.entry(address).or_default(),
+        )
+    }
+
+    /// Applies the changes written in `other`, a layer that was on top of this one.
+    ///
+    /// Values that `other` only read are not applied, as they may be outdated.
+    fn apply(&mut self, other: &Layer) {
+        for (address, other_writes) in &other.writes {
+            let account = &other.accounts[address];
+            if other_writes.cleared {
+                self.accounts.insert(*address, account.clone());
+                self.writes
+                    .entry(*address)
+                    .or_default()
+                    .extend(other_writes);
+                continue;
+            }
+            let (entry, writes) = self.write(*address);
+            if other_writes.info {
+                entry.info.clone_from(&account.info);
+                if !entry.account_state.is_storage_cleared() {
+                    entry.account_state = account.account_state.clone();
+                }
+            }
+            for index in &other_writes.storage {
+                entry.storage.insert(*index, account.storage[index]);
+            }
+            writes.extend(other_writes);
+        }
+        self.contracts.extend(
+            other
+                .contracts
+                .iter()
+                .map(|(code_hash, code)| (*code_hash, code.clone())),
+        );
+        self.block_hashes.extend(&other.block_hashes);
+    }
+}
+
+/// Error returned by [SnapshotDB::commit_branch] when the branch was not created from the
+/// current state of the database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotABranchError;
+
+impl fmt::Display for NotABranchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("branch was not created from the current state of the database")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NotABranchError {}
+
+/// A [Database] with copy-on-write snapshots.
+///
+/// [SnapshotDB::snapshot] freezes the current changes and returns a branch that shares them, in
+/// constant time. Branches are executed and committed independently of each other, and are
+/// either dropped or merged back into their parent with [SnapshotDB::commit_branch].
+///
+/// Changes are stored in layers, each branch reads its own layer first and then the frozen
+/// layers of its parents. Accounts follow the [AccountState] semantics of [super::CacheDB].
+#[derive(Debug)]
+pub struct SnapshotDB<ExtDB> {
+    /// Changes of this branch.
+    layer: Layer,
+    /// Poseidon hashes of the inserted contracts, indexed by their keccak code hash.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    poseidon_code_hashes: crate::primitives::PoseidonCodeHashCache,
+    /// The underlying database, shared by all branches.
+    db: Arc<ExtDB>,
+}
+
+impl<ExtDB: Default> Default for SnapshotDB<ExtDB> {
+    fn default() -> Self {
+        Self::new(ExtDB::default())
+    }
+}
+
+impl<ExtDB> SnapshotDB<ExtDB> {
+    /// Creates a database without changes on top of `db`.
+    pub fn new(db: ExtDB) -> Self {
+        Self {
+            layer: Layer::default(),
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            poseidon_code_hashes: Default::default(),
+            db: Arc::new(db),
+        }
+    }
+
+    /// Returns the underlying database.
+    pub fn db(&self) -> &ExtDB {
+        &self.db
+    }
+
+    /// Returns the number of frozen layers below this branch.
+    pub fn depth(&self) -> usize {
+        self.layer.iter().count() - 1
+    }
+
+    /// Returns a branch of the current state.
+    ///
+    /// The changes of `self` are frozen and shared by both, later changes of either are not
+    /// visible to the other.
+    pub fn snapshot(&mut self) -> Self {
+        if !self.layer.is_empty() {
+            let layer = mem::take(&mut self.layer);
+            self.layer.parent = Some(Arc::new(layer));
+        }
+        Self {
+            layer: Layer {
+                parent: self.layer.parent.clone(),
+                ..Default::default()
+            },
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            poseidon_code_hashes: Default::default(),
+            db: self.db.clone(),
+        }
+    }
+
+    /// Applies the changes of a branch created by [SnapshotDB::snapshot] to `self`.
+    ///
+    /// The changes written in the branch take precedence over the changes made to `self` after
+    /// the snapshot, values that the branch only read are not applied. Fails if the branch was not created from the current state of `self`.
+    pub fn commit_branch(&mut self, branch: &Self) -> Result<(), NotABranchError> {
+        let base = self.layer.parent.as_ref().map(Arc::as_ptr);
+        // layers of the branch above the base of `self`, newest first.
+        let mut layers = Vec::new();
+        for layer in branch.layer.iter() {
+            if Some(layer as *const Layer) == base {
+                break;
+            }
+            layers.push(layer);
+        }
+        let reached_base = base.is_none() || layers.len() <= branch.depth();
+        if !reached_base || !Arc::ptr_eq(&self.db, &branch.db) {
+            return Err(NotABranchError);
+        }
+
+        for layer in layers.into_iter().rev() {
+            self.layer.apply(layer);
+        }
+        Ok(())
+    }
+
+    /// Inserts the account's code into the current layer.
+    fn insert_contract(&mut self, account: &mut AccountInfo) {
+        if let Some(code) = normalize_contract(
+            account,
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            &mut self.poseidon_code_hashes,
+        ) {
+            if self.layer.contract(&account.code_hash).is_none() {
+                self.layer.contracts.insert(account.code_hash, code);
+            }
+        }
+    }
+
+    /// Insert account info but not override storage
+    pub fn insert_account_info(&mut self, address: Address, mut info: AccountInfo) {
+        self.insert_contract(&mut info);
+        let (account, writes) = self.layer.write(address);
+        account.info = info;
+        writes.info = true;
+    }
+
+    /// insert account storage without overriding account info
+    pub fn insert_account_storage(&mut self, address: Address, slot: U256, value: U256) {
+        let (account, writes) = self.layer.write(address);
+        account.storage.insert(slot, value);
+        writes.storage.insert(slot);
+    }
+}
+
+impl<ExtDB> DatabaseCommit for SnapshotDB<ExtDB> {
+    fn commit(&mut self, changes: HashMap<Address, Account>) {
+        for (address, mut account) in changes {
+            if !account.is_touched() {
+                continue;
+            }
+            if account.is_selfdestructed() {
+                self.layer
+                    .accounts
+                    .insert(address, DbAccount::new_not_existing());
+                self.layer.writes.entry(address).or_default().cleared = true;
+                continue;
+            }
+            let is_newly_created = account.is_created();
+            self.insert_contract(&mut account.info);
+
+            let (db_account, writes) = self.layer.write(address);
+            db_account.info = account.info;
+            writes.info = true;
+            writes.cleared |= is_newly_created;
+            writes.storage.extend(account.storage.keys());
+
+            db_account.account_state = if is_newly_created {
+                db_account.storage.clear();
+                AccountState::StorageCleared
+            } else if db_account.account_state.is_storage_cleared() {
+                // Preserve old account state if it already exists
+                AccountState::StorageCleared
+            } else {
+                AccountState::Touched
+            };
+            db_account.storage.extend(
+                account
+                    .storage
+                    .into_iter()
+                    .map(|(key, value)| (key, value.present_value())),
+            );
+        }
+    }
+}
+
+impl<ExtDB: DatabaseRef> Database for SnapshotDB<ExtDB> {
+    type Error = ExtDB::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        if let Some(account) = self.layer.account(&address) {
+            return Ok(account.info());
+        }
+        let account: DbAccount = self.db.basic_ref(address)?.into();
+        let info = account.info();
+        self.layer.accounts.insert(address, account);
+        Ok(info)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        if let Some(code) = self.layer.contract(&code_hash) {
+            return Ok(code.clone());
+        }
+        let code = self.db.code_by_hash_ref(code_hash)?;
+        self.layer.contracts.insert(code_hash, code.clone());
+        Ok(code)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if let Some(value) = self.layer.storage(&address, &index) {
+            return Ok(value);
+        }
+        // account needs to be loaded for us to access slots.
+        if self.basic(address)?.is_none() {
+            return Ok(U256::ZERO);
+        }
+        let value = self.db.storage_ref(address, index)?;
+        self.layer.account_mut(address).storage.insert(index, value);
+        Ok(value)
+    }
+
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        if let Some(hash) = self.layer.block_hash(number) {
+            return Ok(hash);
+        }
+        let hash = self.db.block_hash_ref(number)?;
+        self.layer.block_hashes.insert(number, hash);
+        Ok(hash)
+    }
+}
+
+impl<ExtDB: DatabaseRef> DatabaseRef for SnapshotDB<ExtDB> {
+    type Error = ExtDB::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        match self.layer.account(&address) {
+            Some(account) => Ok(account.info()),
+            None => self.db.basic_ref(address),
+        }
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        match self.layer.contract(&code_hash) {
+            Some(code) => Ok(code.clone()),
+            None => self.db.code_by_hash_ref(code_hash),
+        }
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        match self.layer.storage(&address, &index) {
+            Some(value) => Ok(value),
+            None => self.db.storage_ref(address, index),
+        }
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        match self.layer.block_hash(number) {
+            Some(hash) => Ok(hash),
+            None => self.db.block_hash_ref(number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::EmptyDB,
+        primitives::{Bytes, TxKind},
+        test_utils::{test_call_commit, test_evm_builder, TEST_CALLER, TEST_CONTRACT},
+    };
+
+    // SSTORE(0, SLOAD(0) + 1)
+    const COUNTER: &[u8] = &[0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x00];
+
+    /// Calls the counter contract, which increments slot 0.
+    fn increment(db: &mut SnapshotDB<EmptyDB>) {
+        assert!(test_call_commit(db).unwrap().is_success());
+    }
+
+    fn counter(db: &SnapshotDB<EmptyDB>) -> U256 {
+        db.storage_ref(TEST_CONTRACT, U256::ZERO).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_branches() {
+        let mut db = SnapshotDB::new(EmptyDB::default());
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(COUNTER))),
+        );
+        increment(&mut db);
+
+        let mut first = db.snapshot();
+        let mut second = db.snapshot();
+        assert_eq!(first.depth(), 1);
+        increment(&mut first);
+        increment(&mut first);
+        increment(&mut second);
+        assert_eq!(counter(&db), U256::from(1));
+        assert_eq!(counter(&first), U256::from(3));
+        assert_eq!(counter(&second), U256::from(2));
+
+        // branches of branches are committed with all their layers.
+        let mut nested = first.snapshot();
+        increment(&mut nested);
+        db.commit_branch(&nested).unwrap();
+        assert_eq!(counter(&db), U256::from(4));
+        assert_eq!(counter(&first), U256::from(3));
+
+        // the other branch was not created from the current state.
+        db.snapshot();
+        assert_eq!(db.commit_branch(&second), Err(NotABranchError));
+    }
+
+    #[test]
+    fn test_snapshot_cleared_storage() {
+        let mut db = SnapshotDB::new(EmptyDB::default());
+        db.insert_account_info(TEST_CONTRACT, AccountInfo::default());
+        db.insert_account_storage(TEST_CONTRACT, U256::ZERO, U256::from(1));
+        db.insert_account_storage(TEST_CONTRACT, U256::from(1), U256::from(2));
+
+        let mut branch = db.snapshot();
+        let mut account = Account::from(AccountInfo::default());
+        account.mark_touch();
+        account.mark_created();
+        branch.commit(HashMap::from_iter([(TEST_CONTRACT, account)]));
+        assert_eq!(
+            branch.storage(TEST_CONTRACT, U256::from(1)).unwrap(),
+            U256::ZERO
+        );
+        assert_eq!(
+            db.storage(TEST_CONTRACT, U256::from(1)).unwrap(),
+            U256::from(2)
+        );
+
+        db.commit_branch(&branch).unwrap();
+        assert_eq!(
+            db.storage(TEST_CONTRACT, U256::from(1)).unwrap(),
+            U256::ZERO
+        );
+        assert_eq!(
+            db.layer.account(&TEST_CONTRACT).unwrap().account_state,
+            AccountState::StorageCleared
+        );
+    }
+
+    #[test]
+    fn test_snapshot_created_contract() {
+        // SSTORE(1, 7), then returns the counter code.
+        let mut initcode = vec![
+            0x60, 0x07, 0x60, 0x01, 0x55, 0x60, 0x0a, 0x60, 0x11, 0x60, 0x00, 0x39, 0x60, 0x0a,
+            0x60, 0x00, 0xf3,
+        ];
+        initcode.extend_from_slice(COUNTER);
+        let contract = TEST_CALLER.create(0);
+        let mut db = SnapshotDB::new(EmptyDB::default());
+        let mut evm = test_evm_builder(&mut db, TxKind::Create)
+            .modify_tx_env(|tx| tx.data = initcode.into())
+            .build();
+        assert!(evm.transact_commit().unwrap().is_success());
+        drop(evm);
+
+        // the branch copies the created account, the constructor slot stays visible.
+        let mut branch = db.snapshot();
+        let mut evm = test_evm_builder(&mut branch, TxKind::Call(contract)).build();
+        assert!(evm.transact_commit().unwrap().is_success());
+        drop(evm);
+        assert_eq!(branch.storage_ref(contract, U256::ZERO), Ok(U256::from(1)));
+        assert_eq!(
+            branch.storage_ref(contract, U256::from(1)),
+            Ok(U256::from(7))
+        );
+    }
+
+    #[test]
+    fn test_commit_branch_keeps_parent_writes() {
+        let mut db = SnapshotDB::new(EmptyDB::default());
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(COUNTER))),
+        );
+        db.insert_account_storage(TEST_CONTRACT, U256::from(1), U256::from(1));
+
+        // the branch reads the account and slot 1, then increments slot 0.
+        let mut branch = db.snapshot();
+        assert_eq!(
+            branch.storage(TEST_CONTRACT, U256::from(1)),
+            Ok(U256::from(1))
+        );
+        increment(&mut branch);
+
+        db.insert_account_storage(TEST_CONTRACT, U256::from(1), U256::from(2));
+        db.commit_branch(&branch).unwrap();
+
+        // only the values written by the branch are applied.
+        assert_eq!(counter(&db), U256::from(1));
+        assert_eq!(
+            db.storage_ref(TEST_CONTRACT, U256::from(1)),
+            Ok(U256::from(2))
+        );
+    }
+}
@@ -4,12 +4,16 @@ pub mod bundle_state;
 pub mod cache;
 pub mod cache_account;
 pub mod changes;
+pub mod encoding;
 pub mod plain_account;
 pub mod reverts;
 pub mod state;
 pub mod state_builder;
+pub mod state_root;
+pub mod transaction_journal;
 pub mod transition_account;
 pub mod transition_state;
+pub mod trie;
 
 /// Account status for Block and Bundle states.
 pub use account_status::AccountStatus;
@@ -18,9 +22,13 @@ pub use bundle_state::{BundleBuilder, BundleState, OriginalValuesKnown};
 pub use cache::CacheState;
 pub use cache_account::CacheAccount;
 pub use changes::{PlainStateReverts, PlainStorageChangeset, PlainStorageRevert, StateChangeset};
+pub use encoding::{CompactEncoding, DecodeError, COMPACT_ENCODING_VERSION};
 pub use plain_account::{PlainAccount, StorageSlot, StorageWithOriginalValues};
 pub use reverts::{AccountRevert, RevertToSlot};
 pub use state::{DBBox, State, StateDBBox};
 pub use state_builder::StateBuilder;
+pub use state_root::{state_root, StateRoot};
+pub use transaction_journal::{TransactionCheckpoint, TransactionJournal};
 pub use transition_account::TransitionAccount;
 pub use transition_state::TransitionState;
+pub use trie::{MerkleTrie, EMPTY_ROOT_HASH};
@@ -0,0 +1,712 @@
+//! Compact and deterministic binary encoding of [BundleState], [Reverts], [BundleAccount] and
+//! [Bytecode].
+//!
+//! Integers are encoded as LEB128 varints, 256 bit values with a length prefix and without
+//! leading zeros. Maps and the accounts of each block of [Reverts] are written sorted by key, so
+//! equal values always have the same encoding.
+//! Bytecodes referenced by the accounts of a [BundleState] are written once, in its contracts.
+
+use super::{
+    reverts::{AccountInfoRevert, Reverts},
+    AccountRevert, AccountStatus, BundleAccount, BundleState, RevertToSlot, StorageSlot,
+};
+use crate::primitives::{
+    bitvec::vec::BitVec, eof::Eof, AccountInfo, Address, Bytecode, Bytes, HashMap, JumpTable,
+    LegacyAnalyzedBytecode, B256, KECCAK_EMPTY, U256,
+};
+use core::fmt;
+use std::{sync::Arc, vec::Vec};
+
+/// Version of the encoding, written at the start of [CompactEncoding::to_compact_bytes].
+pub const COMPACT_ENCODING_VERSION: u8 = 1;
+
+/// Returns the enabled features that change the encoding of the account info.
+pub(crate) const fn feature_flags() -> u8 {
+    (cfg!(feature = "scroll") as u8) | ((cfg!(feature = "scroll-poseidon-codehash") as u8) << 1)
+}
+
+/// Error returned when decoding the compact encoding fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ended before the value was decoded.
+    UnexpectedEnd,
+    /// The input has bytes left after the value was decoded.
+    TrailingBytes,
+    /// The input was encoded with an unknown version of the encoding.
+    UnsupportedVersion(u8),
+    /// The input was encoded with different scroll features enabled.
+    FeatureMismatch { expected: u8, found: u8 },
+    /// An enum tag is not valid.
+    InvalidTag { kind: &'static str, tag: u8 },
+    /// A varint does not fit in 64 bits.
+    InvalidVarint,
+    /// An account references a bytecode that is not in the contracts.
+    MissingCode(B256),
+    /// An EOF bytecode could not be decoded.
+    InvalidEof,
+    /// The original length or the jump table of an analyzed bytecode does not match its
+    /// bytecode.
+    InvalidAnalyzedBytecode,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => f.write_str("unexpected end of input"),
+            Self::TrailingBytes => f.write_str("trailing bytes after the encoded value"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported encoding version {version}")
+            }
+            Self::FeatureMismatch { expected, found } => write!(
+                f,
+                "encoded with feature flags {found:#04b}, expected {expected:#04b}"
+            ),
+            Self::InvalidTag { kind, tag } => write!(f, "invalid {kind} tag {tag}"),
+            Self::InvalidVarint => f.write_str("varint overflows 64 bits"),
+            Self::MissingCode(code_hash) => write!(f, "missing bytecode {code_hash}"),
+            Self::InvalidEof => f.write_str("invalid EOF bytecode"),
+            Self::InvalidAnalyzedBytecode => f.write_str("invalid analyzed bytecode"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Compact binary encoding.
+pub trait CompactEncoding: Sized {
+    /// Appends the encoding of the value, without the version header.
+    fn encode_compact(&self, out: &mut Vec<u8>);
+
+    /// Decodes a value written by [CompactEncoding::encode_compact] from the start of the
+    /// input, and advances the input past it.
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, DecodeError>;
+
+    /// Returns the encoding of the value, prefixed with the encoding version and the enabled
+    /// scroll features.
+    fn to_compact_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::from([COMPACT_ENCODING_VERSION, feature_flags()]);
+        self.encode_compact(&mut out);
+        out
+    }
+
+    /// Decodes a value written by [CompactEncoding::to_compact_bytes].
+    ///
+    /// Fails if the version or the scroll features do not match.
+    fn from_compact_bytes(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        let [version, flags] = take::<2>(&mut bytes)?;
+        if version != COMPACT_ENCODING_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        if flags != feature_flags() {
+            return Err(DecodeError::FeatureMismatch {
+                expected: feature_flags(),
+                found: flags,
+            });
+        }
+        let value = Self::decode_compact(&mut bytes)?;
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(value)
+    }
+}
+
+impl CompactEncoding for Bytecode {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::LegacyRaw(bytes) => {
+                out.push(0);
+                write_bytes(out, bytes);
+            }
+            Self::LegacyAnalyzed(analyzed) => {
+                out.push(1);
+                write_bytes(out, analyzed.bytecode());
+                write_varint(out, analyzed.original_len() as u64);
+                let jump_table = &analyzed.jump_table().0;
+                write_varint(out, jump_table.len() as u64);
+                write_bytes(out, jump_table.as_raw_slice());
+            }
+            Self::Eof(eof) => {
+                out.push(2);
+                write_bytes(out, eof.raw());
+            }
+        }
+    }
+
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(match take::<1>(input)?[0] {
+            0 => Self::LegacyRaw(read_bytes(input)?),
+            1 => {
+                let bytecode = read_bytes(input)?;
+                let original_len = read_varint(input)? as usize;
+                let bits = read_varint(input)? as usize;
+                let mut jump_table = BitVec::from_slice(&read_bytes(input)?);
+                if jump_table.len() < bits {
+                    return Err(DecodeError::UnexpectedEnd);
+                }
+                // the bytecode is executed without bound checks against the jump table.
+                if original_len > bytecode.len() || bits != bytecode.len() {
+                    return Err(DecodeError::InvalidAnalyzedBytecode);
+                }
+                jump_table.truncate(bits);
+                Self::LegacyAnalyzed(LegacyAnalyzedBytecode::new(
+                    bytecode,
+                    original_len,
+                    JumpTable(Arc::new(jump_table)),
+                ))
+            }
+            2 => Self::Eof(Arc::new(
+                Eof::decode(read_bytes(input)?).map_err(|_| DecodeError::InvalidEof)?,
+            )),
+            tag => {
+                return Err(DecodeError::InvalidTag {
+                    kind: "bytecode",
+                    tag,
+                })
+            }
+        })
+    }
+}
+
+impl CompactEncoding for BundleAccount {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        encode_bundle_account(self, out, None);
+    }
+
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        decode_bundle_account(input, None)
+    }
+}
+
+impl CompactEncoding for Reverts {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        write_varint(out, self.len() as u64);
+        for transition in self.iter() {
+            write_varint(out, transition.len() as u64);
+            let mut accounts: Vec<_> = transition.iter().collect();
+            accounts.sort_unstable_by_key(|(address, _)| *address);
+            for (address, revert) in accounts {
+                out.extend_from_slice(address.as_slice());
+                encode_account_revert(revert, out);
+            }
+        }
+    }
+
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        let transitions = read_len(input)?;
+        let mut reverts = Vec::with_capacity(transitions);
+        for _ in 0..transitions {
+            let accounts = read_len(input)?;
+            let mut transition = Vec::with_capacity(accounts);
+            for _ in 0..accounts {
+                let address = Address::new(take(input)?);
+                transition.push((address, decode_account_revert(input)?));
+            }
+            reverts.push(transition);
+        }
+        Ok(Self::new(reverts))
+    }
+}
+
+impl CompactEncoding for BundleState {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        let mut contracts: Vec<_> = self.contracts.iter().collect();
+        contracts.sort_unstable_by_key(|(code_hash, _)| *code_hash);
+        write_varint(out, contracts.len() as u64);
+        for (code_hash, code) in contracts {
+            out.extend_from_slice(code_hash.as_slice());
+            code.encode_compact(out);
+        }
+
+        let mut state: Vec<_> = self.state.iter().collect();
+        state.sort_unstable_by_key(|(address, _)| *address);
+        write_varint(out, state.len() as u64);
+        for (address, account) in state {
+            out.extend_from_slice(address.as_slice());
+            encode_bundle_account(account, out, Some(&self.contracts));
+        }
+
+        self.reverts.encode_compact(out);
+        write_varint(out, self.state_size as u64);
+        write_varint(out, self.reverts_size as u64);
+    }
+
+    fn decode_compact(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        let len = read_len(input)?;
+        let mut contracts = HashMap::with_capacity(len);
+        for _ in 0..len {
+            let code_hash = B256::new(take(input)?);
+            contracts.insert(code_hash, Bytecode::decode_compact(input)?);
+        }
+
+        let len = read_len(input)?;
+        let mut state = HashMap::with_capacity(len);
+        for _ in 0..len {
+            let address = Address::new(take(input)?);
+            state.insert(address, decode_bundle_account(input, Some(&contracts))?);
+        }
+
+        Ok(Self {
+            state,
+            contracts,
+            reverts: Reverts::decode_compact(input)?,
+            state_size: read_varint(input)? as usize,
+            reverts_size: read_varint(input)? as usize,
+        })
+    }
+}
+
+fn encode_bundle_account(
+    account: &BundleAccount,
+    out: &mut Vec<u8>,
+    contracts: Option<&HashMap<B256, Bytecode>>,
+) {
+    encode_option_info(account.info.as_ref(), out, contracts);
+    encode_option_info(account.original_info.as_ref(), out, contracts);
+    let mut storage: Vec<_> = account.storage.iter().collect();
+    storage.sort_unstable_by_key(|(index, _)| *index);
+    write_varint(out, storage.len() as u64);
+    for (index, slot) in storage {
+        write_u256(out, *index);
+        write_u256(out, slot.previous_or_original_value);
+        write_u256(out, slot.present_value);
+    }
+    out.push(encode_status(account.status));
+}
+
+fn decode_bundle_account(
+    input: &mut &[u8],
+    contracts: Option<&HashMap<B256, Bytecode>>,
+) -> Result<BundleAccount, DecodeError> {
+    let info = decode_option_info(input, contracts)?;
+    let original_info = decode_option_info(input, contracts)?;
+    let len = read_len(input)?;
+    let mut storage = HashMap::with_capacity(len);
+    for _ in 0..len {
+        let index = read_u256(input)?;
+        let slot = StorageSlot::new_changed(read_u256(input)?, read_u256(input)?);
+        storage.insert(index, slot);
+    }
+    let status = decode_status(take::<1>(input)?[0])?;
+    Ok(BundleAccount {
+        info,
+        original_info,
+        storage,
+        status,
+    })
+}
+
+fn encode_account_revert(revert: &AccountRevert, out: &mut Vec<u8>) {
+    match &revert.account {
+        AccountInfoRevert::DoNothing => out.push(0),
+        AccountInfoRevert::DeleteIt => out.push(1),
+        AccountInfoRevert::RevertTo(info) => {
+            out.push(2);
+            encode_info(info, out, None);
+        }
+    }
+    let mut storage: Vec<_> = revert.storage.iter().collect();
+    storage.sort_unstable_by_key(|(index, _)| *index);
+    write_varint(out, storage.len() as u64);
+    for (index, slot) in storage {
+        write_u256(out, *index);
+        match slot {
+            RevertToSlot::Some(value) => {
+                out.push(0);
+                write_u256(out, *value);
+            }
+            RevertToSlot::Destroyed => out.push(1),
+        }
+    }
+    out.push(encode_status(revert.previous_status));
+    out.push(revert.wipe_storage as u8);
+}
+
+fn decode_account_revert(input: &mut &[u8]) -> Result<AccountRevert, DecodeError> {
+    let account = match take::<1>(input)?[0] {
+        0 => AccountInfoRevert::DoNothing,
+        1 => AccountInfoRevert::DeleteIt,
+        2 => AccountInfoRevert::RevertTo(decode_info(input, None)?),
+        tag => {
+            return Err(DecodeError::InvalidTag {
+                kind: "account revert",
+                tag,
+            })
+        }
+    };
+    let len = read_len(input)?;
+    let mut storage = HashMap::with_capacity(len);
+    for _ in 0..len {
+        let index = read_u256(input)?;
+        let slot = match take::<1>(input)?[0] {
+            0 => RevertToSlot::Some(read_u256(input)?),
+            1 => RevertToSlot::Destroyed,
+            tag => {
+                return Err(DecodeError::InvalidTag {
+                    kind: "storage revert",
+                    tag,
+                })
+            }
+        };
+        storage.insert(index, slot);
+    }
+    Ok(AccountRevert {
+        account,
+        storage,
+        previous_status: decode_status(take::<1>(input)?[0])?,
+        wipe_storage: decode_bool(take::<1>(input)?[0])?,
+    })
+}
+
+/// Account info flags.
+const KECCAK_EMPTY_CODE_HASH: u8 = 1;
+const INLINE_CODE: u8 = 1 << 1;
+const CONTRACT_CODE: u8 = 1 << 2;
+
+fn encode_option_info(
+    info: Option<&AccountInfo>,
+    out: &mut Vec<u8>,
+    contracts: Option<&HashMap<B256, Bytecode>>,
+) {
+    match info {
+        Some(info) => {
+            out.push(1);
+            encode_info(info, out, contracts);
+        }
+        None => out.push(0),
+    }
+}
+
+fn decode_option_info(
+    input: &mut &[u8],
+    contracts: Option<&HashMap<B256, Bytecode>>,
+) -> Result<Option<AccountInfo>, DecodeError> {
+    match decode_bool(take::<1>(input)?[0])? {
+        true => decode_info(input, contracts).map(Some),
+        false => Ok(None),
+    }
+}
+
+/// Encodes the account info, the code is only referenced if it is equal to the one in the
+/// contracts.
+fn encode_info(info: &AccountInfo, out: &mut Vec<u8>, contracts: Option<&HashMap<B256, Bytecode>>) {
+    let mut flags = 0;
+    if info.code_hash == KECCAK_EMPTY {
+        flags |= KECCAK_EMPTY_CODE_HASH;
+    }
+    let inline_code = match &info.code {
+        Some(code)
+            if contracts.and_then(|contracts| contracts.get(&info.code_hash)) == Some(code) =>
+        {
+            flags |= CONTRACT_CODE;
+            None
+        }
+        Some(code) => {
+            flags |= INLINE_CODE;
+            Some(code)
+        }
+        None => None,
+    };
+    out.push(flags);
+    write_u256(out, info.balance);
+    write_varint(out, info.nonce);
+    if info.code_hash != KECCAK_EMPTY {
+        out.extend_from_slice(info.code_hash.as_slice());
+    }
+    #[cfg(feature = "scroll")]
+    write_varint(out, info.code_size as u64);
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    out.extend_from_slice(info.poseidon_code_hash.as_slice());
+    if let Some(code) = inline_code {
+        code.encode_compact(out);
+    }
+}
+
+fn decode_info(
+    input: &mut &[u8],
+    contracts: Option<&HashMap<B256, Bytecode>>,
+) -> Result<AccountInfo, DecodeError> {
+    let flags = take::<1>(input)?[0];
+    if flags & !(KECCAK_EMPTY_CODE_HASH | INLINE_CODE | CONTRACT_CODE) != 0
+        || flags & (INLINE_CODE | CONTRACT_CODE) == INLINE_CODE | CONTRACT_CODE
+    {
+        return Err(DecodeError::InvalidTag {
+            kind: "account info",
+            tag: flags,
+        });
+    }
+    let balance = read_u256(input)?;
+    let nonce = read_varint(input)?;
+    let code_hash = if flags & KECCAK_EMPTY_CODE_HASH != 0 {
+        KECCAK_EMPTY
+    } else {
+        B256::new(take(input)?)
+    };
+    #[cfg(feature = "scroll")]
+    let code_size = read_varint(input)? as usize;
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    let poseidon_code_hash = B256::new(take(input)?);
+    let code = if flags & INLINE_CODE != 0 {
+        Some(Bytecode::decode_compact(input)?)
+    } else if flags & CONTRACT_CODE != 0 {
+        let code = contracts.and_then(|contracts| contracts.get(&code_hash));
+        Some(code.cloned().ok_or(DecodeError::MissingCode(code_hash))?)
+    } else {
+        None
+    };
+    Ok(AccountInfo {
+        balance,
+        nonce,
+        code_hash,
+        #[cfg(feature = "scroll")]
+        code_size,
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        poseidon_code_hash,
+        code,
+    })
+}
+
+fn encode_status(status: AccountStatus) -> u8 {
+    match status {
+        AccountStatus::LoadedNotExisting => 0,
+        AccountStatus::Loaded => 1,
+        AccountStatus::LoadedEmptyEIP161 => 2,
+        AccountStatus::InMemoryChange => 3,
+        AccountStatus::Changed => 4,
+        AccountStatus::Destroyed => 5,
+        AccountStatus::DestroyedChanged => 6,
+        AccountStatus::DestroyedAgain => 7,
+    }
+}
+
+fn decode_status(tag: u8) -> Result<AccountStatus, DecodeError> {
+    Ok(match tag {
+        0 => AccountStatus::LoadedNotExisting,
+        1 => AccountStatus::Loaded,
+        2 => AccountStatus::LoadedEmptyEIP161,
+        3 => AccountStatus::InMemoryChange,
+        4 => AccountStatus::Changed,
+        5 => AccountStatus::Destroyed,
+        6 => AccountStatus::DestroyedChanged,
+        7 => AccountStatus::DestroyedAgain,
+        tag => {
+            return Err(DecodeError::InvalidTag {
+                kind: "account status",
+                tag,
+            })
+        }
+    })
+}
+
+fn decode_bool(tag: u8) -> Result<bool, DecodeError> {
+    match tag {
+        0 => Ok(false),
+        1 => Ok(true),
+        tag => Err(DecodeError::InvalidTag { kind: "bool", tag }),
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = take::<1>(input)?[0];
+        let bits = (byte & 0x7f) as u64;
+        if shift == 63 && bits > 1 {
+            return Err(DecodeError::InvalidVarint);
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(DecodeError::InvalidVarint)
+}
+
+/// Reads a length, which can not be larger than the remaining input as every element takes at
+/// least one byte.
+fn read_len(input: &mut &[u8]) -> Result<usize, DecodeError> {
+    let len = read_varint(input)?;
+    if len > input.len() as u64 {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    Ok(len as usize)
+}
+
+fn write_u256(out: &mut Vec<u8>, value: U256) {
+    let bytes = value.to_be_bytes::<32>();
+    let start = bytes.iter().take_while(|byte| **byte == 0).count();
+    out.push((32 - start) as u8);
+    out.extend_from_slice(&bytes[start..]);
+}
+
+fn read_u256(input: &mut &[u8]) -> Result<U256, DecodeError> {
+    let len = take::<1>(input)?[0] as usize;
+    if len > 32 {
+        return Err(DecodeError::InvalidTag {
+            kind: "u256 length",
+            tag: len as u8,
+        });
+    }
+    let bytes = take_slice(input, len)?;
+    let mut padded = [0u8; 32];
+    padded[32 - len..].copy_from_slice(bytes);
+    Ok(U256::from_be_bytes(padded))
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn read_bytes(input: &mut &[u8]) -> Result<Bytes, DecodeError> {
+    let len = read_len(input)?;
+    take_slice(input, len).map(Bytes::copy_from_slice)
+}
+
+/// Reads `N` bytes from the input.
+pub(crate) fn take<const N: usize>(input: &mut &[u8]) -> Result<[u8; N], DecodeError> {
+    take_slice(input, N).map(|bytes| bytes.try_into().unwrap())
+}
+
+pub(crate) fn take_slice<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], DecodeError> {
+    if input.len() < len {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{states::bundle_state::BundleRetention, EmptyDB, State},
+        primitives::TxKind,
+        test_utils::{test_evm_builder, TEST_CALLER},
+        DatabaseCommit,
+    };
+
+    /// Returns a bundle with a created contract, a changed account and reverts.
+    fn bundle() -> BundleState {
+        let mut state = State::builder()
+            .with_database(EmptyDB::default())
+            .with_bundle_update()
+            .build();
+        state.insert_account(
+            TEST_CALLER,
+            AccountInfo::from_balance(U256::from(1_000_000)),
+        );
+        for _ in 0..2 {
+            let mut evm = test_evm_builder(&mut state, TxKind::Create)
+                .modify_tx_env(|tx| {
+                    // SSTORE(0, 1), RETURN(0, 1) deploying a STOP
+                    tx.data = Bytes::from_static(&[
+                        0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x01, 0x60, 0x00, 0xf3,
+                    ]);
+                })
+                .build();
+            let result = evm.transact().unwrap();
+            assert!(result.result.is_success());
+            drop(evm);
+            state.commit(result.state);
+            state.merge_transitions(BundleRetention::Reverts);
+        }
+        state.take_bundle()
+    }
+
+    #[test]
+    fn test_bundle_state_round_trip() {
+        let mut bundle = bundle();
+        // reverts of each block are decoded sorted by address.
+        bundle.reverts.sort();
+        assert_eq!(bundle.contracts.len(), 1);
+        let encoded = bundle.to_compact_bytes();
+        assert_eq!(
+            BundleState::from_compact_bytes(&encoded),
+            Ok(bundle.clone())
+        );
+        // the encoding does not depend on the iteration order of the maps.
+        let mut reordered = BundleState::from_compact_bytes(&encoded).unwrap();
+        let mut accounts: Vec<_> = reordered.state.into_iter().collect();
+        accounts.reverse();
+        reordered.state = accounts.into_iter().collect();
+        for transition in reordered.reverts.iter_mut() {
+            transition.reverse();
+        }
+        assert_ne!(reordered.reverts, bundle.reverts);
+        assert_eq!(reordered.to_compact_bytes(), encoded);
+
+        for account in bundle.state.values() {
+            let encoded = account.to_compact_bytes();
+            assert_eq!(
+                BundleAccount::from_compact_bytes(&encoded).as_ref(),
+                Ok(account)
+            );
+        }
+        let encoded = bundle.reverts.to_compact_bytes();
+        assert_eq!(Reverts::from_compact_bytes(&encoded), Ok(bundle.reverts));
+    }
+
+    #[test]
+    fn test_bytecode_round_trip() {
+        let raw = Bytecode::new_raw(Bytes::from_static(&[0x60, 0x01, 0x56, 0x5b]));
+        for code in [
+            raw.clone(),
+            crate::interpreter::analysis::to_analysed(raw),
+            Bytecode::new(),
+        ] {
+            assert_eq!(
+                Bytecode::from_compact_bytes(&code.to_compact_bytes()),
+                Ok(code)
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_analyzed_bytecode() {
+        let bytecode = Bytes::from_static(&[0x60, 0x01, 0x56, 0x5b]);
+        for (original_len, bits) in [(5, 4), (4, 3), (4, 8)] {
+            let code = Bytecode::LegacyAnalyzed(LegacyAnalyzedBytecode::new(
+                bytecode.clone(),
+                original_len,
+                JumpTable(Arc::new(BitVec::repeat(false, bits))),
+            ));
+            assert_eq!(
+                Bytecode::from_compact_bytes(&code.to_compact_bytes()),
+                Err(DecodeError::InvalidAnalyzedBytecode)
+            );
+        }
+    }
+
+    #[test]
+    fn test_header_mismatch() {
+        let mut encoded = Bytecode::new().to_compact_bytes();
+        encoded[1] ^= 1;
+        assert!(matches!(
+            Bytecode::from_compact_bytes(&encoded),
+            Err(DecodeError::FeatureMismatch { .. })
+        ));
+        encoded[0] = COMPACT_ENCODING_VERSION + 1;
+        assert_eq!(
+            Bytecode::from_compact_bytes(&encoded),
+            Err(DecodeError::UnsupportedVersion(
+                COMPACT_ENCODING_VERSION + 1
+            ))
+        );
+        let encoded = bundle().to_compact_bytes();
+        assert_eq!(
+            BundleState::from_compact_bytes(&encoded[..encoded.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+    }
+}
@@ -1,6 +1,10 @@
 use super::{
-    bundle_state::BundleRetention, cache::CacheState, plain_account::PlainStorage, BundleState,
-    CacheAccount, StateBuilder, TransitionAccount, TransitionState,
+    bundle_state::BundleRetention,
+    cache::CacheState,
+    plain_account::PlainStorage,
+    transaction_journal::{CacheAccountRevert, TransactionChanges},
+    BundleState, CacheAccount, StateBuilder, TransactionCheckpoint, TransactionJournal,
+    TransitionAccount, TransitionState,
 };
 use crate::db::EmptyDB;
 use revm_interpreter::primitives::{
@@ -56,6 +60,10 @@ pub struct State<DB> {
     /// This map can be used to give different values for block hashes if in case
     /// The fork block is different or some blocks are not saved inside database.
     pub block_hashes: BTreeMap<u64, B256>,
+    /// Changes of each transaction applied since the last merge of transitions, if enabled.
+    ///
+    /// Allows reverting the last transactions without rebuilding the block.
+    pub transaction_journal: Option<TransactionJournal>,
 }
 
 // Have ability to call State::builder without having to specify the type.
@@ -87,11 +95,16 @@ impl<DB: Database> State<DB> {
     ) -> Result<(), DB::Error> {
         // make transition and update cache state
         let mut transitions = Vec::new();
+        let mut accounts = Vec::new();
         for (address, balance) in balances {
             if balance == 0 {
                 continue;
             }
+            let journal = self.transaction_journal.is_some();
             let original_account = self.load_cache_account(address)?;
+            if journal {
+                accounts.push((address, CacheAccountRevert::info(original_account)));
+            }
             transitions.push((
                 address,
                 original_account
@@ -100,9 +113,7 @@ impl<DB: Database> State<DB> {
             ))
         }
         // append transition
-        if let Some(s) = self.transition_state.as_mut() {
-            s.add_transitions(transitions)
-        }
+        self.add_transitions(transitions, accounts);
         Ok(())
     }
 
@@ -115,17 +126,20 @@ impl<DB: Database> State<DB> {
     ) -> Result<Vec<u128>, DB::Error> {
         // make transition and update cache state
         let mut transitions = Vec::new();
+        let mut accounts = Vec::new();
         let mut balances = Vec::new();
         for address in addresses {
+            let journal = self.transaction_journal.is_some();
             let original_account = self.load_cache_account(address)?;
+            if journal {
+                accounts.push((address, CacheAccountRevert::info(original_account)));
+            }
             let (balance, transition) = original_account.drain_balance();
             balances.push(balance);
             transitions.push((address, transition))
         }
         // append transition
-        if let Some(s) = self.transition_state.as_mut() {
-            s.add_transitions(transitions)
-        }
+        self.add_transitions(transitions, accounts);
         Ok(balances)
     }
 
@@ -153,11 +167,99 @@ impl<DB: Database> State<DB> {
     }
 
     /// Apply evm transitions to transition state.
+    ///
+    /// The changes of the cache that led to the transitions are not known, so the transactions
+    /// applied before can no longer be reverted with [State::revert_transactions].
     pub fn apply_transition(&mut self, transitions: Vec<(Address, TransitionAccount)>) {
         // add transition to transition state.
         if let Some(s) = self.transition_state.as_mut() {
             s.add_transitions(transitions)
         }
+        if let Some(journal) = self.transaction_journal.as_mut() {
+            journal.on_apply_transition(self.transition_state.as_ref());
+        }
+    }
+
+    /// Adds the transitions to the transition state and records them in the transaction
+    /// journal, together with the previous state of the changed cache accounts.
+    fn add_transitions(
+        &mut self,
+        transitions: Vec<(Address, TransitionAccount)>,
+        accounts: Vec<(Address, CacheAccountRevert)>,
+    ) {
+        if let Some(journal) = self.transaction_journal.as_mut() {
+            journal.push(TransactionChanges {
+                transitions: if self.transition_state.is_some() {
+                    transitions.clone()
+                } else {
+                    Vec::new()
+                },
+                accounts,
+            });
+        }
+        // add transition to transition state.
+        if let Some(s) = self.transition_state.as_mut() {
+            s.add_transitions(transitions)
+        }
+    }
+
+    /// Returns the checkpoint after the last applied transaction, or `None` if the transaction
+    /// journal is not enabled.
+    ///
+    /// See [StateBuilder::with_transaction_journal].
+    pub fn transaction_checkpoint(&self) -> Option<TransactionCheckpoint> {
+        self.transaction_journal
+            .as_ref()
+            .map(TransactionJournal::checkpoint)
+    }
+
+    /// Reverts the transactions applied after the checkpoint.
+    ///
+    /// Returns `false` and reverts nothing if the checkpoint was taken before the last merge of
+    /// transitions or [State::apply_transition], or if the transaction journal is not enabled.
+    pub fn revert_to_transaction_checkpoint(&mut self, checkpoint: TransactionCheckpoint) -> bool {
+        let Some(count) = self
+            .transaction_journal
+            .as_ref()
+            .and_then(|journal| journal.transactions_since(checkpoint))
+        else {
+            return false;
+        };
+        self.revert_transactions(count);
+        true
+    }
+
+    /// Reverts the last `count` transactions applied since the last merge of transitions.
+    ///
+    /// Each [DatabaseCommit::commit], [State::increment_balances] and [State::drain_balances]
+    /// call counts as one transaction, transactions applied before the last
+    /// [State::apply_transition] or merge of transitions can't be reverted. The cache and the transition
+    /// state are restored to their state before those transactions, the database and the
+    /// bundle are not touched.
+    ///
+    /// Returns the number of reverted transactions, which is zero if the transaction journal is
+    /// not enabled.
+    pub fn revert_transactions(&mut self, count: usize) -> usize {
+        let Some(journal) = self.transaction_journal.as_mut() else {
+            return 0;
+        };
+        let mut reverted = 0;
+        for changes in journal.pop(count) {
+            for (address, revert) in changes.accounts.into_iter().rev() {
+                if let Some(account) = self.cache.accounts.get_mut(&address) {
+                    revert.revert(account);
+                }
+            }
+            reverted += 1;
+        }
+        // transitions are merged per account, so the transition state is rebuilt from the
+        // remaining transactions.
+        if reverted > 0 {
+            if let Some(transition_state) = self.transition_state.as_mut() {
+                *transition_state = journal.transition_state();
+            }
+        }
+        reverted
     }
 
     /// Take all transitions and merge them inside bundle state.
@@ -169,6 +271,9 @@ impl<DB: Database> State<DB> {
             self.bundle_state
                 .apply_transitions_and_create_reverts(transition_state, retention);
         }
+        if let Some(journal) = self.transaction_journal.as_mut() {
+            journal.on_merge();
+        }
     }
 
     pub fn load_cache_account(&mut self, address: Address) -> Result<&mut CacheAccount, DB::Error> {
@@ -292,8 +397,19 @@ impl<DB: Database> Database for State<DB> {
 
 impl<DB: Database> DatabaseCommit for State<DB> {
     fn commit(&mut self, evm_state: HashMap<Address, Account>) {
+        let mut accounts = Vec::new();
+        if self.transaction_journal.is_some() {
+            for (address, evm_account) in evm_state.iter().filter(|(_, a)| a.is_touched()) {
+                if let Some(account) = self.cache.accounts.get(address) {
+                    accounts.push((
+                        *address,
+                        CacheAccountRevert::evm_account(account, evm_account),
+                    ));
+                }
+            }
+        }
         let transitions = self.cache.apply_evm_state(evm_state);
-        self.apply_transition(transitions);
+        self.add_transitions(transitions, accounts);
     }
 }
 
@@ -789,4 +905,82 @@ mod tests {
             )])])
         )
     }
+
+    #[test]
+    fn revert_transactions() {
+        use crate::{
+            primitives::{Bytes, TxKind},
+            test_utils::{test_evm_builder, TEST_CALLER, TEST_CONTRACT},
+        };
+
+        let build = || {
+            let mut state = State::builder()
+                .with_bundle_update()
+                .with_transaction_journal()
+                .build();
+            state.insert_account(
+                TEST_CALLER,
+                AccountInfo::from_balance(U256::from(1_000_000)),
+            );
+            // SSTORE(0, CALLVALUE)
+            state.insert_account(
+                TEST_CONTRACT,
+                AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                    0x34, 0x60, 0x00, 0x55, 0x00,
+                ]))),
+            );
+            state
+        };
+        let transact = |state: &mut State<EmptyDB>, transact_to: TxKind, value: u64| {
+            let mut evm = test_evm_builder(state, transact_to)
+                .modify_tx_env(|tx| {
+                    tx.value = U256::from(value);
+                    // SSTORE(0, 1)
+                    tx.data = Bytes::from_static(&[0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+                })
+                .build();
+            assert!(evm.transact_commit().unwrap().is_success());
+        };
+
+        let mut state = build();
+        transact(&mut state, TxKind::Call(TEST_CONTRACT), 1);
+        let checkpoint = state.transaction_checkpoint().unwrap();
+        transact(&mut state, TxKind::Create, 0);
+        transact(&mut state, TxKind::Call(TEST_CONTRACT), 3);
+        assert_eq!(state.storage(TEST_CONTRACT, U256::ZERO), Ok(U256::from(3)));
+
+        assert_eq!(state.revert_transactions(1), 1);
+        assert_eq!(state.storage(TEST_CONTRACT, U256::ZERO), Ok(U256::from(1)));
+        let created = TEST_CALLER.create(1);
+        assert!(state.basic(created).unwrap().is_some());
+
+        assert!(state.revert_to_transaction_checkpoint(checkpoint));
+        assert_eq!(state.basic(created), Ok(None));
+        assert_eq!(state.basic(TEST_CALLER).unwrap().unwrap().nonce, 1);
+
+        // the bundle only contains the first transaction.
+        let mut expected = build();
+        transact(&mut expected, TxKind::Call(TEST_CONTRACT), 1);
+        state.merge_transitions(BundleRetention::Reverts);
+        expected.merge_transitions(BundleRetention::Reverts);
+        let (mut bundle, mut expected) = (state.take_bundle(), expected.take_bundle());
+        bundle.reverts.sort();
+        expected.reverts.sort();
+        assert_eq!(bundle, expected);
+
+        // checkpoints do not survive merges.
+        assert!(!state.revert_to_transaction_checkpoint(checkpoint));
+        assert_eq!(state.revert_transactions(1), 0);
+
+        // applied transitions can't be reverted and are kept when reverting later transactions.
+        let checkpoint = state.transaction_checkpoint().unwrap();
+        let mut transitions = build();
+        transact(&mut transitions, TxKind::Call(TEST_CONTRACT), 4);
+        let transitions = transitions.transition_state.take().unwrap();
+        state.apply_transition(transitions.transitions.clone().into_iter().collect());
+        assert!(!state.revert_to_transaction_checkpoint(checkpoint));
+        transact(&mut state, TxKind::Call(TEST_CONTRACT), 5);
+        assert_eq!(state.revert_transactions(2), 1);
+        assert_eq!(state.transition_state, Some(transitions));
+    }
 }
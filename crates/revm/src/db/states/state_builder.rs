@@ -1,4 +1,6 @@
-use super::{cache::CacheState, state::DBBox, BundleState, State, TransitionState};
+use super::{
+    cache::CacheState, state::DBBox, BundleState, State, TransactionJournal, TransitionState,
+};
 use crate::db::EmptyDB;
 use revm_interpreter::primitives::{
     db::{Database, DatabaseRef, WrapDatabaseRef},
@@ -28,6 +30,9 @@ pub struct StateBuilder<DB> {
     with_background_transition_merge: bool,
     /// If we want to set different block hashes
     with_block_hashes: BTreeMap<u64, B256>,
+    /// Do we want to record changes of each transaction so they can be reverted.
+    /// Default is false.
+    with_transaction_journal: bool,
 }
 
 impl StateBuilder<EmptyDB> {
@@ -57,6 +62,7 @@ impl<DB: Database> StateBuilder<DB> {
             with_bundle_update: false,
             with_background_transition_merge: false,
             with_block_hashes: BTreeMap::new(),
+            with_transaction_journal: false,
         }
     }
 
@@ -72,6 +78,7 @@ impl<DB: Database> StateBuilder<DB> {
             with_bundle_update: self.with_bundle_update,
             with_background_transition_merge: self.with_background_transition_merge,
             with_block_hashes: self.with_block_hashes,
+            with_transaction_journal: self.with_transaction_journal,
         }
     }
 
@@ -143,6 +150,17 @@ impl<DB: Database> StateBuilder<DB> {
         }
     }
 
+    /// Records the changes of each transaction so that the last transactions can be reverted
+    /// until transitions are merged.
+    ///
+    /// See [State::revert_transactions].
+    pub fn with_transaction_journal(self) -> Self {
+        Self {
+            with_transaction_journal: true,
+            ..self
+        }
+    }
+
     pub fn with_block_hashes(self, block_hashes: BTreeMap<u64, B256>) -> Self {
         Self {
             with_block_hashes: block_hashes,
@@ -166,6 +184,9 @@ impl<DB: Database> StateBuilder<DB> {
             bundle_state: self.with_bundle_prestate.unwrap_or_default(),
             use_preloaded_bundle,
             block_hashes: self.with_block_hashes,
+            transaction_journal: self
+                .with_transaction_journal
+                .then(TransactionJournal::default),
         }
     }
 }
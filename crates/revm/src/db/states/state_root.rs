@@ -0,0 +1,292 @@
+use super::{
+    trie::{rlp_bytes, rlp_list, rlp_u256, MerkleTrie},
+    BundleState, PlainAccount, TransitionState,
+};
+use crate::primitives::{keccak256, AccountInfo, Address, HashMap, HashSet, B256, U256};
+use std::vec::Vec;
+
+/// Ethereum state root, kept up to date incrementally between blocks.
+///
+/// The tries of all accounts are kept in memory, applying a [BundleState] or a [TransitionState]
+/// only updates the accounts and storage slots that changed. Storage of destroyed accounts is
+/// wiped before their new slots are applied.
+#[derive(Clone, Debug, Default)]
+pub struct StateRoot {
+    accounts: HashMap<Address, TrieAccount>,
+    trie: MerkleTrie,
+    /// Accounts whose leaf needs to be updated in the account trie.
+    changed: HashSet<Address>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct TrieAccount {
+    nonce: u64,
+    balance: U256,
+    code_hash: B256,
+    storage: MerkleTrie,
+}
+
+impl StateRoot {
+    /// Creates the state root of an empty state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the account, replacing its storage with the given slots.
+    pub fn insert_account(
+        &mut self,
+        address: Address,
+        info: &AccountInfo,
+        storage: impl IntoIterator<Item = (U256, U256)>,
+    ) {
+        self.update_account(address, Some(info), true, storage);
+    }
+
+    /// Removes the account and its storage.
+    pub fn remove_account(&mut self, address: Address) {
+        self.update_account(address, None, true, []);
+    }
+
+    /// Applies the changes of the bundle.
+    ///
+    /// The bundle has to be built on top of the state this root was computed for.
+    pub fn apply_bundle(&mut self, bundle: &BundleState) {
+        for (address, account) in &bundle.state {
+            let wipe = account.was_destroyed();
+            let storage = account
+                .storage
+                .iter()
+                .filter(|(_, slot)| wipe || slot.is_changed())
+                .map(|(index, slot)| (*index, slot.present_value));
+            self.update_account(*address, account.info.as_ref(), wipe, storage);
+        }
+    }
+
+    /// Applies the changes of the transitions.
+    ///
+    /// The transitions have to be built on top of the state this root was computed for.
+    pub fn apply_transitions(&mut self, transitions: &TransitionState) {
+        for (address, account) in &transitions.transitions {
+            let wipe = account.storage_was_destroyed;
+            let storage = account
+                .storage
+                .iter()
+                .filter(|(_, slot)| wipe || slot.is_changed())
+                .map(|(index, slot)| (*index, slot.present_value));
+            self.update_account(*address, account.info.as_ref(), wipe, storage);
+        }
+    }
+
+    fn update_account(
+        &mut self,
+        address: Address,
+        info: Option<&AccountInfo>,
+        wipe_storage: bool,
+        storage: impl IntoIterator<Item = (U256, U256)>,
+    ) {
+        let Some(info) = info else {
+            self.accounts.remove(&address);
+            self.changed.remove(&address);
+            self.trie.remove(keccak256(address));
+            return;
+        };
+        let account = self.accounts.entry(address).or_default();
+        account.nonce = info.nonce;
+        account.balance = info.balance;
+        account.code_hash = info.code_hash;
+        if wipe_storage {
+            account.storage = MerkleTrie::new();
+        }
+        for (index, value) in storage {
+            let key = keccak256(index.to_be_bytes::<32>());
+            if value.is_zero() {
+                account.storage.remove(key);
+            } else {
+                let mut encoded = Vec::with_capacity(33);
+                rlp_u256(&mut encoded, value);
+                account.storage.insert(key, encoded);
+            }
+        }
+        self.changed.insert(address);
+    }
+
+    /// Returns the state root, hashing only the nodes that changed since the last call.
+    pub fn root(&mut self) -> B256 {
+        for address in self.changed.drain() {
+            let account = self.accounts.get_mut(&address).unwrap();
+            let storage_root = account.storage.root();
+            self.trie
+                .insert(keccak256(address), account.encode(storage_root));
+        }
+        self.trie.root()
+    }
+
+    /// Returns the state root computed from scratch, without any of the cached hashes.
+    ///
+    /// Used to check the incremental root.
+    pub fn rebuild_root(&self) -> B256 {
+        let mut trie = MerkleTrie::new();
+        for (address, account) in &self.accounts {
+            let storage_root = account.storage.rebuild().root();
+            trie.insert(keccak256(address), account.encode(storage_root));
+        }
+        trie.root()
+    }
+}
+
+impl TrieAccount {
+    /// Returns the RLP encoding of the account leaf.
+    fn encode(&self, storage_root: B256) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(110);
+        rlp_u256(&mut payload, U256::from(self.nonce));
+        rlp_u256(&mut payload, self.balance);
+        rlp_bytes(&mut payload, storage_root.as_slice());
+        rlp_bytes(&mut payload, self.code_hash.as_slice());
+        let mut out = Vec::with_capacity(payload.len() + 2);
+        rlp_list(&mut out, &payload);
+        out
+    }
+}
+
+/// Computes the state root of the accounts from scratch.
+pub fn state_root<'a>(accounts: impl IntoIterator<Item = (Address, &'a PlainAccount)>) -> B256 {
+    let mut state_root = StateRoot::new();
+    for (address, account) in accounts {
+        state_root.insert_account(
+            address,
+            &account.info,
+            account
+                .storage
+                .iter()
+                .map(|(index, value)| (*index, *value)),
+        );
+    }
+    state_root.root()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{
+            states::{bundle_state::BundleRetention, trie::EMPTY_ROOT_HASH, StorageSlot},
+            AccountStatus, EmptyDB, State, TransitionAccount,
+        },
+        primitives::{b256, Bytecode, Bytes, TxKind, KECCAK_EMPTY},
+        test_utils::{test_evm_builder, TEST_CALLER, TEST_CONTRACT},
+        DatabaseCommit,
+    };
+
+    #[test]
+    fn test_empty_root() {
+        assert_eq!(StateRoot::new().root(), EMPTY_ROOT_HASH);
+        assert_eq!(state_root([]), EMPTY_ROOT_HASH);
+        assert_eq!(MerkleTrie::new().root(), EMPTY_ROOT_HASH);
+    }
+
+    #[test]
+    fn test_known_root() {
+        // root computed with the `triehash` crate.
+        let mut state_root = StateRoot::new();
+        state_root.insert_account(
+            Address::with_last_byte(1),
+            &AccountInfo::new(U256::from(1), 0, KECCAK_EMPTY, Bytecode::new()),
+            [],
+        );
+        state_root.insert_account(
+            Address::with_last_byte(2),
+            &AccountInfo::new(U256::ZERO, 1, B256::with_last_byte(2), Bytecode::new()),
+            [(U256::ZERO, U256::from(5)), (U256::from(1), U256::MAX)],
+        );
+        let expected = b256!("6c8b6c3c817b1ed3d355fb1b327222d39474d2de7eb21a06361a30dfe601c30e");
+        assert_eq!(state_root.root(), expected);
+        assert_eq!(state_root.rebuild_root(), expected);
+    }
+
+    #[test]
+    fn test_destroyed_storage_wiped() {
+        let address = Address::with_last_byte(1);
+        let info = AccountInfo::from_balance(U256::from(1));
+        let mut state_root = StateRoot::new();
+        state_root.insert_account(
+            address,
+            &info,
+            [(U256::ZERO, U256::from(1)), (U256::from(1), U256::from(2))],
+        );
+
+        let transition = TransitionAccount {
+            info: Some(info.clone()),
+            status: AccountStatus::DestroyedChanged,
+            storage: [(
+                U256::from(2),
+                StorageSlot::new_changed(U256::ZERO, U256::from(3)),
+            )]
+            .into_iter()
+            .collect(),
+            storage_was_destroyed: true,
+            ..Default::default()
+        };
+        state_root.apply_transitions(&TransitionState::single(address, transition));
+
+        let mut expected = StateRoot::new();
+        expected.insert_account(address, &info, [(U256::from(2), U256::from(3))]);
+        assert_eq!(state_root.root(), expected.root());
+        assert_eq!(state_root.rebuild_root(), expected.root());
+    }
+
+    #[test]
+    fn test_incremental_root() {
+        let mut state = State::builder()
+            .with_database(EmptyDB::default())
+            .with_bundle_update()
+            .build();
+        // SSTORE(CALLVALUE, NUMBER), SSTORE(1, 0)
+        let code = Bytecode::new_raw(Bytes::from_static(&[
+            0x43, 0x34, 0x55, 0x60, 0x00, 0x60, 0x01, 0x55, 0x00,
+        ]));
+        let contract_info = AccountInfo::from_bytecode(code);
+        let caller_info = AccountInfo::from_balance(U256::from(1_000_000_000_000_000_000u128));
+        state.insert_account(TEST_CONTRACT, contract_info.clone());
+        state.insert_account(TEST_CALLER, caller_info.clone());
+
+        let mut state_root = StateRoot::new();
+        state_root.insert_account(TEST_CONTRACT, &contract_info, []);
+        state_root.insert_account(TEST_CALLER, &caller_info, []);
+        for block in 1..=16u64 {
+            let mut evm = test_evm_builder(&mut state, TxKind::Call(TEST_CONTRACT))
+                .modify_block_env(|block_env| block_env.number = U256::from(block))
+                .modify_tx_env(|tx| {
+                    tx.value = U256::from(block % 5);
+                    tx.gas_price = U256::ZERO;
+                })
+                .build();
+            let result = evm.transact().unwrap();
+            assert!(result.result.is_success());
+            drop(evm);
+            state.commit(result.state);
+
+            if block % 3 == 0 {
+                state_root.apply_transitions(state.transition_state.as_ref().unwrap());
+                state.merge_transitions(BundleRetention::PlainState);
+                state.take_bundle();
+            } else {
+                state.merge_transitions(BundleRetention::PlainState);
+                state_root.apply_bundle(&state.take_bundle());
+            }
+            assert_eq!(state_root.root(), state_root.rebuild_root());
+        }
+
+        let accounts: Vec<_> = state
+            .cache
+            .accounts
+            .iter()
+            .filter_map(|(address, account)| Some((*address, account.account.clone()?)))
+            .collect();
+        let expected = super::state_root(
+            accounts
+                .iter()
+                .map(|(address, account)| (*address, account)),
+        );
+        assert_eq!(state_root.root(), expected);
+    }
+}
@@ -0,0 +1,183 @@
+use super::{
+    plain_account::PlainStorage, CacheAccount, PlainAccount, TransitionAccount, TransitionState,
+};
+use crate::db::AccountStatus;
+use revm_interpreter::primitives::{Account, AccountInfo, Address, U256};
+use std::vec::Vec;
+
+/// Changes of the transactions applied to the [crate::db::State] since transitions were last
+/// merged, used to revert the transactions one by one.
+#[derive(Clone, Debug, Default)]
+pub struct TransactionJournal {
+    /// Incremented when the recorded transactions can no longer be reverted, checkpoints of
+    /// previous generations can't be reverted to.
+    generation: u64,
+    /// Transition state that the recorded transactions were applied on top of, `None` if it
+    /// was empty.
+    base_transitions: Option<TransitionState>,
+    /// Changes of each transaction, in the order they were applied.
+    transactions: Vec<TransactionChanges>,
+}
+
+/// Changes of a single transaction.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TransactionChanges {
+    /// Transitions that were added to the transition state.
+    pub(crate) transitions: Vec<(Address, TransitionAccount)>,
+    /// Previous state of the cached accounts that were changed.
+    pub(crate) accounts: Vec<(Address, CacheAccountRevert)>,
+}
+
+/// Checkpoint of the [crate::db::State] between two transactions.
+///
+/// It is only valid until the transitions are merged into the bundle, or until transitions are
+/// applied with [crate::db::State::apply_transition].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TransactionCheckpoint {
+    generation: u64,
+    transactions: usize,
+}
+
+impl TransactionJournal {
+    /// Returns the number of transactions that can be reverted.
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Returns `true` if no transaction can be reverted.
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Returns the checkpoint after the last transaction.
+    pub fn checkpoint(&self) -> TransactionCheckpoint {
+        TransactionCheckpoint {
+            generation: self.generation,
+            transactions: self.transactions.len(),
+        }
+    }
+
+    /// Returns the number of transactions applied after the checkpoint, or `None` if the
+    /// checkpoint was taken before the last merge or applied transitions.
+    pub fn transactions_since(&self, checkpoint: TransactionCheckpoint) -> Option<usize> {
+        if checkpoint.generation != self.generation {
+            return None;
+        }
+        self.transactions.len().checked_sub(checkpoint.transactions)
+    }
+
+    pub(crate) fn push(&mut self, changes: TransactionChanges) {
+        self.transactions.push(changes);
+    }
+
+    /// Removes the changes of the last `count` transactions, returned with the latest first.
+    pub(crate) fn pop(&mut self, count: usize) -> impl Iterator<Item = TransactionChanges> {
+        let count = count.min(self.transactions.len());
+        self.transactions
+            .split_off(self.transactions.len() - count)
+            .into_iter()
+            .rev()
+    }
+
+    /// Returns the transition state without the reverted transactions, the transitions of the
+    /// remaining transactions are added on top of the base transition state.
+    pub(crate) fn transition_state(&self) -> TransitionState {
+        let mut transition_state = self.base_transitions.clone().unwrap_or_default();
+        for changes in &self.transactions {
+            transition_state.add_transitions(changes.transitions.clone());
+        }
+        transition_state
+    }
+
+    /// Clears the journal after the transitions were merged into the bundle.
+    pub(crate) fn on_merge(&mut self) {
+        self.generation += 1;
+        self.base_transitions = None;
+        self.transactions.clear();
+    }
+
+    /// Clears the journal after transitions that can't be reverted were applied, the
+    /// `transition_state` including them becomes the base of the next transactions.
+    pub(crate) fn on_apply_transition(&mut self, transition_state: Option<&TransitionState>) {
+        self.generation += 1;
+        self.base_transitions = transition_state.cloned();
+        self.transactions.clear();
+    }
+}
+
+/// Previous state of a cached account.
+#[derive(Clone, Debug)]
+pub(crate) struct CacheAccountRevert {
+    info: Option<AccountInfo>,
+    status: AccountStatus,
+    storage: StorageRevert,
+}
+
+#[derive(Clone, Debug)]
+enum StorageRevert {
+    /// Storage was replaced, the whole previous storage is kept.
+    Replaced(PlainStorage),
+    /// Only these slots changed, with their previous value if it was cached.
+    Slots(Vec<(U256, Option<U256>)>),
+}
+
+impl CacheAccountRevert {
+    /// Creates the revert for the change of the account info, the storage is unchanged.
+    pub(crate) fn info(account: &CacheAccount) -> Self {
+        Self {
+            info: account.account_info(),
+            status: account.status,
+            storage: StorageRevert::Slots(Vec::new()),
+        }
+    }
+
+    /// Creates the revert for the EVM account about to be applied to the cached account.
+    ///
+    /// Storage is only cloned if the account is destroyed, created or touched while empty, as
+    /// these replace the whole storage.
+    pub(crate) fn evm_account(account: &CacheAccount, evm_account: &Account) -> Self {
+        let storage = account.account.as_ref().map(|account| &account.storage);
+        let storage = if evm_account.is_selfdestructed()
+            || evm_account.is_created()
+            || evm_account.is_empty()
+        {
+            StorageRevert::Replaced(storage.cloned().unwrap_or_default())
+        } else {
+            StorageRevert::Slots(
+                evm_account
+                    .storage
+                    .iter()
+                    .filter(|(_, slot)| slot.is_changed())
+                    .map(|(index, _)| (*index, storage.and_then(|s| s.get(index).copied())))
+                    .collect(),
+            )
+        };
+        Self {
+            info: account.account_info(),
+            status: account.status,
+            storage,
+        }
+    }
+
+    /// Restores the cached account to its previous state.
+    pub(crate) fn revert(self, account: &mut CacheAccount) {
+        account.status = self.status;
+        let storage = account.account.take().map(|account| account.storage);
+        account.account = self.info.map(|info| {
+            let storage = match self.storage {
+                StorageRevert::Replaced(storage) => storage,
+                StorageRevert::Slots(slots) => {
+                    let mut storage = storage.unwrap_or_default();
+                    for (index, value) in slots {
+                        match value {
+                            Some(value) => storage.insert(index, value),
+                            None => storage.remove(&index),
+                        };
+                    }
+                    storage
+                }
+            };
+            PlainAccount { info, storage }
+        });
+    }
+}
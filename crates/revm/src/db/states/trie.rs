@@ -0,0 +1,350 @@
+//! In-memory Merkle Patricia Trie with cached node hashes, used to compute state roots
+//! incrementally.
+
+use crate::primitives::{b256, keccak256, B256, U256};
+use std::{boxed::Box, vec::Vec};
+
+/// Root hash of an empty trie, the keccak hash of the RLP encoded empty string.
+pub const EMPTY_ROOT_HASH: B256 =
+    b256!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421");
+
+/// Merkle Patricia Trie with fixed length keys, as used by the state and storage tries.
+///
+/// Nodes keep their hash between updates, only the nodes on the path of an updated key are
+/// hashed again when the root is computed.
+#[derive(Clone, Debug, Default)]
+pub struct MerkleTrie {
+    root: Option<Node>,
+}
+
+impl MerkleTrie {
+    /// Creates an empty trie.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the trie has no values.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Inserts the value at the given key.
+    pub fn insert(&mut self, key: B256, value: Vec<u8>) {
+        let path = nibbles(&key);
+        self.root = Some(Node::insert(self.root.take(), &path, value));
+    }
+
+    /// Removes the value at the given key.
+    pub fn remove(&mut self, key: B256) {
+        let path = nibbles(&key);
+        self.root = self.root.take().and_then(|root| root.remove(&path));
+    }
+
+    /// Returns the root hash of the trie.
+    pub fn root(&mut self) -> B256 {
+        match &mut self.root {
+            None => EMPTY_ROOT_HASH,
+            Some(root) => {
+                let reference = root.reference();
+                if reference.len() == 33 {
+                    B256::from_slice(&reference[1..])
+                } else {
+                    keccak256(reference)
+                }
+            }
+        }
+    }
+
+    /// Returns a new trie built from the values of this one, without any cached hashes.
+    pub fn rebuild(&self) -> Self {
+        let mut leaves = Vec::new();
+        if let Some(root) = &self.root {
+            root.leaves(&mut Vec::new(), &mut leaves);
+        }
+        let mut trie = Self::new();
+        for (path, value) in leaves {
+            trie.root = Some(Node::insert(trie.root.take(), &path, value));
+        }
+        trie
+    }
+}
+
+/// Node of the trie, with the cached RLP reference used by its parent.
+#[derive(Clone, Debug)]
+struct Node {
+    kind: NodeKind,
+    /// RLP encoding of the node if it is shorter than 32 bytes, otherwise the RLP encoded hash.
+    reference: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug)]
+enum NodeKind {
+    Leaf { path: Vec<u8>, value: Vec<u8> },
+    Extension { path: Vec<u8>, child: Box<Node> },
+    Branch { children: Box<[Option<Node>; 16]> },
+}
+
+impl Node {
+    fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            reference: None,
+        }
+    }
+
+    fn leaf(path: &[u8], value: Vec<u8>) -> Self {
+        Self::new(NodeKind::Leaf {
+            path: path.to_vec(),
+            value,
+        })
+    }
+
+    /// Returns an extension node, or the child itself if the path is empty.
+    ///
+    /// Paths of leaf and extension children are merged into the new node.
+    fn extension(path: &[u8], child: Node) -> Self {
+        if path.is_empty() {
+            return child;
+        }
+        match child.kind {
+            NodeKind::Leaf {
+                path: child_path,
+                value,
+            } => Self::new(NodeKind::Leaf {
+                path: [path, &child_path].concat(),
+                value,
+            }),
+            NodeKind::Extension {
+                path: child_path,
+                child,
+            } => Self::new(NodeKind::Extension {
+                path: [path, &child_path].concat(),
+                child,
+            }),
+            kind @ NodeKind::Branch { .. } => Self::new(NodeKind::Extension {
+                path: path.to_vec(),
+                child: Box::new(Self {
+                    kind,
+                    reference: child.reference,
+                }),
+            }),
+        }
+    }
+
+    fn branch() -> Box<[Option<Node>; 16]> {
+        Box::default()
+    }
+
+    fn insert(node: Option<Node>, path: &[u8], value: Vec<u8>) -> Node {
+        let Some(node) = node else {
+            return Self::leaf(path, value);
+        };
+        match node.kind {
+            NodeKind::Leaf {
+                path: leaf_path,
+                value: leaf_value,
+            } => {
+                if leaf_path == path {
+                    return Self::leaf(path, value);
+                }
+                let common = common_prefix(&leaf_path, path);
+                let mut children = Self::branch();
+                children[leaf_path[common] as usize] =
+                    Some(Self::leaf(&leaf_path[common + 1..], leaf_value));
+                children[path[common] as usize] = Some(Self::leaf(&path[common + 1..], value));
+                Self::extension(&path[..common], Self::new(NodeKind::Branch { children }))
+            }
+            NodeKind::Extension {
+                path: extension_path,
+                child,
+            } => {
+                let common = common_prefix(&extension_path, path);
+                if common == extension_path.len() {
+                    let child = Self::insert(Some(*child), &path[common..], value);
+                    return Self::extension(&extension_path, child);
+                }
+                let mut children = Self::branch();
+                children[extension_path[common] as usize] =
+                    Some(Self::extension(&extension_path[common + 1..], *child));
+                children[path[common] as usize] = Some(Self::leaf(&path[common + 1..], value));
+                Self::extension(&path[..common], Self::new(NodeKind::Branch { children }))
+            }
+            NodeKind::Branch { mut children } => {
+                let index = path[0] as usize;
+                children[index] = Some(Self::insert(children[index].take(), &path[1..], value));
+                Self::new(NodeKind::Branch { children })
+            }
+        }
+    }
+
+    fn remove(self, path: &[u8]) -> Option<Node> {
+        match self.kind {
+            NodeKind::Leaf {
+                path: ref leaf_path,
+                ..
+            } => (leaf_path != path).then_some(self),
+            NodeKind::Extension {
+                path: ref extension_path,
+                ..
+            } if !path.starts_with(extension_path) => Some(self),
+            NodeKind::Extension {
+                path: extension_path,
+                child,
+            } => child
+                .remove(&path[extension_path.len()..])
+                .map(|child| Self::extension(&extension_path, child)),
+            NodeKind::Branch { mut children } => {
+                let index = path[0] as usize;
+                let Some(child) = children[index].take() else {
+                    return Some(Self {
+                        kind: NodeKind::Branch { children },
+                        reference: self.reference,
+                    });
+                };
+                children[index] = child.remove(&path[1..]);
+
+                let mut remaining = children.iter().enumerate().filter(|(_, c)| c.is_some());
+                match (remaining.next(), remaining.next()) {
+                    (None, _) => None,
+                    // a branch with a single child is merged with it.
+                    (Some((index, _)), None) => {
+                        let child = children[index].take().unwrap();
+                        Some(Self::extension(&[index as u8], child))
+                    }
+                    _ => Some(Self::new(NodeKind::Branch { children })),
+                }
+            }
+        }
+    }
+
+    /// Returns the RLP reference of the node, hashing it if it is not cached.
+    fn reference(&mut self) -> &[u8] {
+        if self.reference.is_none() {
+            let encoded = self.encode();
+            let reference = if encoded.len() < 32 {
+                encoded
+            } else {
+                let mut reference = Vec::with_capacity(33);
+                rlp_bytes(&mut reference, keccak256(&encoded).as_slice());
+                reference
+            };
+            self.reference = Some(reference);
+        }
+        self.reference.as_deref().unwrap()
+    }
+
+    /// Returns the RLP encoding of the node.
+    fn encode(&mut self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        match &mut self.kind {
+            NodeKind::Leaf { path, value } => {
+                rlp_bytes(&mut payload, &hex_prefix(path, true));
+                rlp_bytes(&mut payload, value);
+            }
+            NodeKind::Extension { path, child } => {
+                rlp_bytes(&mut payload, &hex_prefix(path, false));
+                payload.extend_from_slice(child.reference());
+            }
+            NodeKind::Branch { children } => {
+                for child in children.iter_mut() {
+                    match child {
+                        Some(child) => payload.extend_from_slice(child.reference()),
+                        None => payload.push(EMPTY_STRING_CODE),
+                    }
+                }
+                // branches never have a value, as all keys have the same length.
+                payload.push(EMPTY_STRING_CODE);
+            }
+        }
+        let mut out = Vec::with_capacity(payload.len() + 3);
+        rlp_list(&mut out, &payload);
+        out
+    }
+
+    /// Collects the full paths and values of the leaves under this node.
+    fn leaves(&self, prefix: &mut Vec<u8>, out: &mut Vec<(Vec<u8>, Vec<u8>)>) {
+        match &self.kind {
+            NodeKind::Leaf { path, value } => {
+                out.push(([&prefix[..], path].concat(), value.clone()))
+            }
+            NodeKind::Extension { path, child } => {
+                let len = prefix.len();
+                prefix.extend_from_slice(path);
+                child.leaves(prefix, out);
+                prefix.truncate(len);
+            }
+            NodeKind::Branch { children } => {
+                for (index, child) in children.iter().enumerate() {
+                    if let Some(child) = child {
+                        prefix.push(index as u8);
+                        child.leaves(prefix, out);
+                        prefix.pop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the nibbles of the key.
+fn nibbles(key: &B256) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+/// Encodes the nibbles with the hex prefix encoding.
+fn hex_prefix(path: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 0x20 } else { 0 };
+    let mut out = Vec::with_capacity(path.len() / 2 + 1);
+    let rest = if path.len() % 2 == 1 {
+        out.push(flag | 0x10 | path[0]);
+        &path[1..]
+    } else {
+        out.push(flag);
+        path
+    };
+    out.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+    out
+}
+
+const EMPTY_STRING_CODE: u8 = 0x80;
+const EMPTY_LIST_CODE: u8 = 0xc0;
+
+fn rlp_header(out: &mut Vec<u8>, code: u8, len: usize) {
+    if len < 56 {
+        out.push(code + len as u8);
+    } else {
+        let len_bytes = len.to_be_bytes();
+        let len_bytes = &len_bytes[len_bytes.iter().take_while(|b| **b == 0).count()..];
+        out.push(code + 55 + len_bytes.len() as u8);
+        out.extend_from_slice(len_bytes);
+    }
+}
+
+/// RLP encodes a byte string.
+pub(crate) fn rlp_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    if let [byte @ 0..=0x7f] = bytes {
+        out.push(*byte);
+    } else {
+        rlp_header(out, EMPTY_STRING_CODE, bytes.len());
+        out.extend_from_slice(bytes);
+    }
+}
+
+/// RLP encodes a list from the concatenated RLP encodings of its items.
+pub(crate) fn rlp_list(out: &mut Vec<u8>, payload: &[u8]) {
+    rlp_header(out, EMPTY_LIST_CODE, payload.len());
+    out.extend_from_slice(payload);
+}
+
+/// RLP encodes an integer, without leading zeros.
+pub(crate) fn rlp_u256(out: &mut Vec<u8>, value: U256) {
+    let bytes = value.to_be_bytes::<32>();
+    let start = bytes.iter().take_while(|b| **b == 0).count();
+    rlp_bytes(out, &bytes[start..]);
+}
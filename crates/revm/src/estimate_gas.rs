@@ -0,0 +1,257 @@
+//! Gas estimation, same as the `eth_estimateGas` RPC method.
+
+use crate::{
+    interpreter::gas::CALL_STIPEND,
+    primitives::{
+        Bytes, EVMError, ExecutionResult, HaltReason, InvalidTransaction, ResultAndState, U256,
+    },
+    Database, Evm,
+};
+
+/// Result of [Evm::estimate_gas].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GasEstimation {
+    /// Lowest gas limit the transaction succeeds with.
+    Success {
+        gas: u64,
+        /// L1 data fee of the transaction, paid on top of the gas.
+        #[cfg(feature = "scroll")]
+        l1_fee: U256,
+    },
+    /// Transaction reverts with the maximum gas limit.
+    Revert { output: Bytes, gas_used: u64 },
+    /// Transaction halts with the maximum gas limit.
+    ///
+    /// [HaltReason::OutOfGas] means that the required gas is above the block gas limit or
+    /// above what the caller can pay for.
+    Halt { reason: HaltReason, gas_used: u64 },
+}
+
+impl<EXT, DB: Database> Evm<'_, EXT, DB> {
+    /// Estimates the gas limit the current transaction needs to succeed.
+    ///
+    /// The transaction is executed with the block gas limit capped by what the caller can pay
+    /// for. If it succeeds, the lowest successful gas limit is found with a binary search.
+    /// With the Scroll handler the L1 data fee is deducted from the caller balance before the
+    /// cap is computed.
+    ///
+    /// Nothing is committed to the database and the transaction is left unchanged.
+    pub fn estimate_gas(&mut self) -> Result<GasEstimation, EVMError<DB::Error>> {
+        let gas_limit = self.tx().gas_limit;
+        let estimation = self.estimate_gas_inner();
+        self.tx_mut().gas_limit = gas_limit;
+        estimation
+    }
+
+    fn estimate_gas_inner(&mut self) -> Result<GasEstimation, EVMError<DB::Error>> {
+        #[cfg(feature = "scroll")]
+        let l1_fee = self.l1_fee()?;
+        #[cfg(not(feature = "scroll"))]
+        let l1_fee = U256::ZERO;
+
+        let mut hi = self.gas_allowance(l1_fee)?;
+
+        self.tx_mut().gas_limit = hi;
+        let (gas_used, gas_refunded) = match self.transact()?.result {
+            ExecutionResult::Success {
+                gas_used,
+                gas_refunded,
+                ..
+            } => (gas_used, gas_refunded),
+            ExecutionResult::Revert { output, gas_used } => {
+                return Ok(GasEstimation::Revert { output, gas_used })
+            }
+            ExecutionResult::Halt { reason, gas_used } => {
+                return Ok(GasEstimation::Halt { reason, gas_used })
+            }
+        };
+
+        // gas used is reduced by the refund, so it is never enough.
+        let mut lo = gas_used.saturating_sub(1);
+
+        // EIP-150 retains 1/64 of the gas in each call, so the gas needed is usually a bit
+        // more than the gas spent. Try that before searching.
+        let optimistic = (gas_used + gas_refunded + CALL_STIPEND) * 64 / 63;
+        if optimistic < hi {
+            if self.succeeds_with(optimistic)? {
+                hi = optimistic;
+            } else {
+                lo = optimistic;
+            }
+        }
+
+        while lo + 1 < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.succeeds_with(mid)? {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+
+        Ok(GasEstimation::Success {
+            gas: hi,
+            #[cfg(feature = "scroll")]
+            l1_fee,
+        })
+    }
+
+    /// Returns `true` if the transaction succeeds with the given gas limit.
+    ///
+    /// Out of gas, reverts and gas limits below the intrinsic gas are all failures, as they
+    /// can be caused by a too low gas limit.
+    fn succeeds_with(&mut self, gas_limit: u64) -> Result<bool, EVMError<DB::Error>> {
+        self.tx_mut().gas_limit = gas_limit;
+        match self.transact() {
+            Ok(ResultAndState { result, .. }) => Ok(result.is_success()),
+            Err(EVMError::Transaction(InvalidTransaction::CallGasCostMoreThanGasLimit)) => {
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the block gas limit capped by the gas the caller can pay for.
+    fn gas_allowance(&mut self, l1_fee: U256) -> Result<u64, EVMError<DB::Error>> {
+        let block_gas_limit = u64::try_from(self.block().gas_limit).unwrap_or(u64::MAX);
+        let gas_price = self.tx().gas_price;
+        if gas_price.is_zero() {
+            return Ok(block_gas_limit);
+        }
+
+        let caller = self.tx().caller;
+        let balance = self
+            .context
+            .evm
+            .inner
+            .db
+            .basic(caller)
+            .map_err(EVMError::Database)?
+            .map(|info| info.balance)
+            .unwrap_or_default();
+        let available = balance
+            .saturating_sub(self.tx().value)
+            .saturating_sub(l1_fee);
+        let allowance = u64::try_from(available / gas_price).unwrap_or(u64::MAX);
+        Ok(block_gas_limit.min(allowance))
+    }
+
+    /// Returns the L1 data fee of the transaction, zero if it is not executed by the Scroll
+    /// handler or is an L1 message.
+    #[cfg(feature = "scroll")]
+    fn l1_fee(&mut self) -> Result<U256, EVMError<DB::Error>> {
+        let spec_id = self.spec_id();
+        let tx = &self.context.evm.inner.env.tx;
+        if !self.handler.cfg.is_scroll || tx.scroll.is_l1_msg {
+            return Ok(U256::ZERO);
+        }
+        let Some(rlp_bytes) = tx.scroll.rlp_bytes.clone() else {
+            return Ok(U256::ZERO);
+        };
+        let l1_block_info =
+            crate::scroll::L1BlockInfo::try_fetch(&mut self.context.evm.inner.db, spec_id)
+                .map_err(EVMError::Database)?;
+        Ok(l1_block_info.calculate_tx_l1_cost(&rlp_bytes, spec_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        primitives::{address, AccountInfo, Bytecode, OutOfGasError, TxKind},
+        test_utils::{test_evm_builder, TEST_CONTRACT},
+    };
+
+    fn evm_with_code(code: Vec<u8>) -> Evm<'static, (), CacheDB<EmptyDB>> {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(code.into())),
+        );
+        test_evm_builder(db, TxKind::Call(TEST_CONTRACT))
+            .modify_block_env(|block| block.gas_limit = U256::from(1_000_000))
+            .modify_tx_env(|tx| tx.gas_limit = 30_000)
+            .build()
+    }
+
+    #[test]
+    fn test_estimate_gas() {
+        // SSTORE(0, 1)
+        let mut evm = evm_with_code(vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+        let GasEstimation::Success { gas, .. } = evm.estimate_gas().unwrap() else {
+            panic!("expected success");
+        };
+        // new storage slot costs 22100.
+        assert_eq!(gas, 21_000 + 3 + 3 + 22_100);
+        assert_eq!(evm.tx().gas_limit, 30_000);
+
+        evm.tx_mut().gas_limit = gas;
+        assert!(evm.transact().unwrap().result.is_success());
+        evm.tx_mut().gas_limit = gas - 1;
+        assert!(!evm.transact().unwrap().result.is_success());
+    }
+
+    #[test]
+    fn test_estimate_gas_nested_call() {
+        let callee = address!("3000000000000000000000000000000000000000");
+        // CALL(GAS, callee, 0, 0, 0, 0, 0) and revert if it failed.
+        let mut code = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+        ];
+        code.extend_from_slice(callee.as_slice());
+        code.extend_from_slice(&[
+            0x5a, 0xf1, 0x15, 0x60, 0x26, 0x57, 0x00, 0x5b, 0x60, 0x00, 0x60, 0x00, 0xfd,
+        ]);
+        let mut evm = evm_with_code(code);
+        // SSTORE(0, 1)
+        evm.db_mut().insert_account_info(
+            callee,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                0x60, 0x01, 0x60, 0x00, 0x55, 0x00,
+            ]))),
+        );
+
+        let GasEstimation::Success { gas, .. } = evm.estimate_gas().unwrap() else {
+            panic!("expected success");
+        };
+        evm.tx_mut().gas_limit = gas;
+        let result = evm.transact().unwrap().result;
+        assert!(result.is_success());
+        // 1/64 of the gas is retained by the caller.
+        assert!(gas > result.gas_used());
+        evm.tx_mut().gas_limit = gas - 1;
+        assert!(!evm.transact().unwrap().result.is_success());
+    }
+
+    #[test]
+    fn test_estimate_gas_revert() {
+        // REVERT(0, 0)
+        let mut evm = evm_with_code(vec![0x60, 0x00, 0x60, 0x00, 0xfd]);
+        assert_eq!(
+            evm.estimate_gas().unwrap(),
+            GasEstimation::Revert {
+                output: Bytes::new(),
+                gas_used: 21_006
+            }
+        );
+    }
+
+    #[test]
+    fn test_estimate_gas_allowance() {
+        // infinite loop
+        let mut evm = evm_with_code(vec![0x5b, 0x60, 0x00, 0x56]);
+        evm.tx_mut().gas_price = U256::from(10);
+        let caller = evm.tx().caller;
+        evm.db_mut()
+            .insert_account_info(caller, AccountInfo::from_balance(U256::from(500_000)));
+        assert_eq!(
+            evm.estimate_gas().unwrap(),
+            GasEstimation::Halt {
+                reason: HaltReason::OutOfGas(OutOfGasError::Basic),
+                gas_used: 50_000
+            }
+        );
+    }
+}
@@ -27,6 +27,11 @@ pub fn load_accounts<SPEC: Spec, EXT, DB: Database>(
 ) -> Result<(), EVMError<DB::Error>> {
     // set journaling state flag.
     context.evm.journaled_state.set_spec_id(SPEC::SPEC_ID);
+    #[cfg(feature = "scroll")]
+    {
+        context.evm.journaled_state.check_account_consistency =
+            context.evm.inner.env.cfg.check_account_consistency;
+    }
 
     // load coinbase
     // EIP-3651: Warm COINBASE. Starts the `COINBASE` address warm
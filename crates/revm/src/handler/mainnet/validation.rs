@@ -17,6 +17,13 @@ pub fn validate_env<SPEC: Spec, DB: Database>(env: &Env) -> Result<(), EVMError<
 pub fn validate_tx_against_state<SPEC: Spec, EXT, DB: Database>(
     context: &mut Context<EXT, DB>,
 ) -> Result<(), EVMError<DB::Error>> {
+    // set before the caller is loaded so it is checked as well.
+    #[cfg(feature = "scroll")]
+    {
+        context.evm.inner.journaled_state.check_account_consistency =
+            context.evm.inner.env.cfg.check_account_consistency;
+    }
+
     // load acc
     let tx_caller = context.evm.env.tx.caller;
     let (caller_account, _) = context
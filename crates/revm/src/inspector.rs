@@ -1,10 +1,18 @@
+mod access_list;
+mod call_tracer;
+mod coverage;
 #[cfg(feature = "std")]
 mod customprinter;
 #[cfg(all(feature = "std", feature = "serde-json"))]
 mod eip3155;
 mod gas;
+mod gas_profiler;
 mod handler_register;
+mod multi_inspector;
 mod noop;
+mod prestate_tracer;
+#[cfg(all(feature = "std", feature = "serde-json"))]
+mod struct_logger;
 
 pub use handler_register::{inspector_handle_register, GetInspector};
 
@@ -19,12 +27,26 @@ use auto_impl::auto_impl;
 
 /// [Inspector] implementations.
 pub mod inspectors {
+    pub use super::access_list::AccessListInspector;
+    pub use super::call_tracer::{
+        CallTraceFrame, CallTraceKind, CallTraceLog, CallTracer, CallTracerConfig,
+    };
+    pub use super::coverage::{
+        BranchCoverage, CodeCoverage, CoverageInspector, CoverageMap, SourceMap,
+    };
     #[cfg(feature = "std")]
     pub use super::customprinter::CustomPrintTracer;
     #[cfg(all(feature = "std", feature = "serde-json"))]
     pub use super::eip3155::TracerEip3155;
     pub use super::gas::GasInspector;
+    pub use super::gas_profiler::{GasProfiler, GasProfilerConfig};
+    pub use super::multi_inspector::MultiInspector;
     pub use super::noop::NoOpInspector;
+    pub use super::prestate_tracer::{
+        PrestateAccount, PrestateDiff, PrestateFrame, PrestateTracer, PrestateTracerConfig,
+    };
+    #[cfg(all(feature = "std", feature = "serde-json"))]
+    pub use super::struct_logger::{StructLogger, StructLoggerConfig};
 }
 
 /// EVM [Interpreter] callbacks.
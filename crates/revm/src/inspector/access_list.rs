@@ -0,0 +1,151 @@
+//! Access list [Inspector], used to generate [EIP-2930] access lists.
+//!
+//! [EIP-2930]: https://eips.ethereum.org/EIPS/eip-2930
+
+use crate::{
+    interpreter::{opcode, CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter},
+    primitives::{db::Database, AccessListItem, Address, HashSet, SpecId, TxKind, B256},
+    EvmContext, Inspector,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
+};
+
+/// [Inspector] that records every address and storage key accessed by the transaction.
+///
+/// Addresses that are warm by [EIP-2929] and [EIP-3651] rules are excluded from the access
+/// list: the sender, the recipient, the precompiles and the coinbase. Storage keys of these
+/// addresses are still included.
+///
+/// [EIP-2929]: https://eips.ethereum.org/EIPS/eip-2929
+/// [EIP-3651]: https://eips.ethereum.org/EIPS/eip-3651
+#[derive(Clone, Debug, Default)]
+pub struct AccessListInspector {
+    /// Addresses that are warm without being in the access list.
+    excluded: HashSet<Address>,
+    /// Accessed addresses and their storage keys.
+    access_list: BTreeMap<Address, BTreeSet<B256>>,
+}
+
+impl AccessListInspector {
+    /// Creates a new inspector that starts from the given access list.
+    pub fn new(access_list: &[AccessListItem]) -> Self {
+        Self {
+            excluded: HashSet::new(),
+            access_list: access_list
+                .iter()
+                .map(|item| {
+                    (
+                        item.address,
+                        item.storage_keys.iter().copied().collect::<BTreeSet<_>>(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the addresses excluded from the access list.
+    pub fn excluded(&self) -> &HashSet<Address> {
+        &self.excluded
+    }
+
+    /// Returns the generated access list.
+    ///
+    /// Excluded addresses are only part of the list if any of their storage keys was accessed.
+    pub fn access_list(&self) -> Vec<AccessListItem> {
+        self.access_list
+            .iter()
+            .filter(|(address, keys)| !keys.is_empty() || !self.excluded.contains(*address))
+            .map(|(address, keys)| AccessListItem {
+                address: *address,
+                storage_keys: keys.iter().copied().collect(),
+            })
+            .collect()
+    }
+
+    /// Excludes addresses that are warm at the start of the transaction.
+    fn exclude_warm_addresses<DB: Database>(&mut self, context: &EvmContext<DB>) {
+        let env = &context.env;
+        self.excluded.insert(env.tx.caller);
+        if let TxKind::Call(to) = env.tx.transact_to {
+            self.excluded.insert(to);
+        }
+        if SpecId::enabled(context.spec_id(), SpecId::SHANGHAI) {
+            self.excluded.insert(env.block.coinbase);
+        }
+        self.excluded
+            .extend(context.precompiles.addresses().copied());
+    }
+
+    fn insert_address(&mut self, address: Address) {
+        self.access_list.entry(address).or_default();
+    }
+}
+
+impl<DB: Database> Inspector<DB> for AccessListInspector {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        match interp.current_opcode() {
+            opcode::SLOAD | opcode::SSTORE => {
+                if let Ok(slot) = interp.stack.peek(0) {
+                    self.access_list
+                        .entry(interp.contract.target_address)
+                        .or_default()
+                        .insert(B256::from(slot));
+                }
+            }
+            opcode::EXTCODECOPY
+            | opcode::EXTCODEHASH
+            | opcode::EXTCODESIZE
+            | opcode::BALANCE
+            | opcode::SELFDESTRUCT => {
+                if let Ok(address) = interp.stack.peek(0) {
+                    self.insert_address(Address::from_word(B256::from(address)));
+                }
+            }
+            opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL | opcode::STATICCALL => {
+                if let Ok(address) = interp.stack.peek(1) {
+                    self.insert_address(Address::from_word(B256::from(address)));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        if context.journaled_state.depth() == 0 {
+            self.exclude_warm_addresses(context);
+        }
+        None
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        if context.journaled_state.depth() == 0 {
+            self.exclude_warm_addresses(context);
+        }
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        // created contract is the recipient of the create transaction.
+        if context.journaled_state.depth() == 0 {
+            if let Some(address) = outcome.address {
+                self.excluded.insert(address);
+            }
+        }
+        outcome
+    }
+}
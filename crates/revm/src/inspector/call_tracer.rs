@@ -0,0 +1,562 @@
+//! Geth `callTracer` compatible [Inspector].
+
+use crate::{
+    interpreter::{
+        CallInputs, CallOutcome, CallScheme, CallValue, CreateInputs, CreateOutcome,
+        InstructionResult, Interpreter, InterpreterResult,
+    },
+    primitives::{db::Database, Address, Bytes, CreateScheme, Log, SpecId, B256, U256},
+    EvmContext, Inspector,
+};
+use std::{string::String, vec::Vec};
+
+/// Configuration of the [CallTracer], same as the geth `callTracer` options.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct CallTracerConfig {
+    /// Trace only the top call and ignore all subcalls.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub only_top_call: bool,
+    /// Include the logs emitted by each call.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub with_log: bool,
+}
+
+/// Kind of a [CallTraceFrame].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "UPPERCASE"))]
+pub enum CallTraceKind {
+    #[default]
+    Call,
+    StaticCall,
+    DelegateCall,
+    CallCode,
+    Create,
+    Create2,
+    SelfDestruct,
+}
+
+impl From<CallScheme> for CallTraceKind {
+    fn from(scheme: CallScheme) -> Self {
+        match scheme {
+            CallScheme::Call | CallScheme::ExtCall => Self::Call,
+            CallScheme::StaticCall | CallScheme::ExtStaticCall => Self::StaticCall,
+            CallScheme::DelegateCall | CallScheme::ExtDelegateCall => Self::DelegateCall,
+            CallScheme::CallCode => Self::CallCode,
+        }
+    }
+}
+
+impl From<CreateScheme> for CallTraceKind {
+    fn from(scheme: CreateScheme) -> Self {
+        match scheme {
+            CreateScheme::Create => Self::Create,
+            CreateScheme::Create2 { .. } => Self::Create2,
+        }
+    }
+}
+
+/// Log emitted inside a [CallTraceFrame].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CallTraceLog {
+    /// Address of the contract that emitted the log.
+    pub address: Address,
+    /// Topics of the log.
+    pub topics: Vec<B256>,
+    /// Data of the log.
+    pub data: Bytes,
+    /// Number of subcalls made by the frame before the log was emitted.
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex_u64"))]
+    pub position: u64,
+}
+
+/// Single call of the call tree, serializes to the geth `callTracer` format.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct CallTraceFrame {
+    /// Kind of the call.
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub kind: CallTraceKind,
+    /// Caller.
+    pub from: Address,
+    /// Callee, or the created contract. `None` if the creation failed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub to: Option<Address>,
+    /// Transferred value, `None` for `STATICCALL` and `DELEGATECALL`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub value: Option<U256>,
+    /// Gas limit of the call.
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex_u64"))]
+    pub gas: u64,
+    /// Gas used by the call, including its subcalls.
+    #[cfg_attr(feature = "serde", serde(with = "serde_hex_u64"))]
+    pub gas_used: u64,
+    /// Call data, or the init code of a creation.
+    pub input: Bytes,
+    /// Returned data, `None` if it is empty.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub output: Option<Bytes>,
+    /// Geth error message if the call failed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub error: Option<String>,
+    /// Decoded revert reason if the call reverted with one.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub revert_reason: Option<String>,
+    /// Subcalls in execution order.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub calls: Vec<CallTraceFrame>,
+    /// Logs emitted by the call, only recorded if [CallTracerConfig::with_log] is set.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub logs: Vec<CallTraceLog>,
+}
+
+impl CallTraceFrame {
+    /// Sets the outcome of the call.
+    fn set_result(&mut self, result: &InterpreterResult) {
+        self.gas_used = self.gas.saturating_sub(result.gas.remaining());
+        if result.is_ok() {
+            if !result.output.is_empty() {
+                self.output = Some(result.output.clone());
+            }
+            return;
+        }
+
+        self.error = Some(geth_error(result.result).into());
+        if matches!(self.kind, CallTraceKind::Create | CallTraceKind::Create2) {
+            self.to = None;
+        }
+        // logs of failed calls are reverted.
+        self.clear_logs();
+        if result.is_revert() && !result.output.is_empty() {
+            self.revert_reason = decode_revert_reason(&result.output);
+            self.output = Some(result.output.clone());
+        }
+    }
+
+    /// Removes logs of this frame and of all its subcalls.
+    fn clear_logs(&mut self) {
+        self.logs.clear();
+        for call in &mut self.calls {
+            call.clear_logs();
+        }
+    }
+}
+
+/// [Inspector] that builds the call tree of a transaction, like the geth `callTracer`.
+///
+/// The result is available with [CallTracer::frame] after the transaction is executed.
+#[derive(Clone, Debug, Default)]
+pub struct CallTracer {
+    config: CallTracerConfig,
+    /// Frames of the calls that are currently executing.
+    stack: Vec<CallTraceFrame>,
+    /// Top frame of the last traced transaction.
+    frame: Option<CallTraceFrame>,
+}
+
+impl CallTracer {
+    /// Creates a tracer with the given config.
+    pub fn new(config: CallTracerConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the call tree of the last traced transaction.
+    pub fn frame(&self) -> Option<&CallTraceFrame> {
+        self.frame.as_ref()
+    }
+
+    /// Takes the call tree of the last traced transaction.
+    pub fn take_frame(&mut self) -> Option<CallTraceFrame> {
+        self.frame.take()
+    }
+
+    /// Resets the tracer so it can be used for the next transaction.
+    pub fn clear(&mut self) {
+        self.stack.clear();
+        self.frame = None;
+    }
+
+    /// Pops the finished frame and attaches it to its parent.
+    fn pop<DB: Database>(&mut self, context: &EvmContext<DB>, result: &InterpreterResult) {
+        let Some(mut frame) = self.stack.pop() else {
+            return;
+        };
+        frame.set_result(result);
+
+        let Some(parent) = self.stack.last_mut() else {
+            // top frame reports the gas of the whole transaction.
+            frame.gas = context.env.tx.gas_limit;
+            frame.gas_used = tx_gas_used(context, result);
+            self.frame = Some(frame);
+            return;
+        };
+        if !self.config.only_top_call {
+            parent.calls.push(frame);
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for CallTracer {
+    fn log(&mut self, _interp: &mut Interpreter, _context: &mut EvmContext<DB>, log: &Log) {
+        if !self.config.with_log {
+            return;
+        }
+        if let Some(frame) = self.stack.last_mut() {
+            frame.logs.push(CallTraceLog {
+                address: log.address,
+                topics: log.topics().to_vec(),
+                data: log.data.data.clone(),
+                position: frame.calls.len() as u64,
+            });
+        }
+    }
+
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        let kind = CallTraceKind::from(inputs.scheme);
+        // `DELEGATECALL` and `CALLCODE` are made by the current contract.
+        let from = match kind {
+            CallTraceKind::DelegateCall | CallTraceKind::CallCode => inputs.target_address,
+            _ => inputs.caller,
+        };
+        let value = match (kind, &inputs.value) {
+            (CallTraceKind::StaticCall, _) | (_, CallValue::Apparent(_)) => None,
+            (_, CallValue::Transfer(value)) => Some(*value),
+        };
+        self.stack.push(CallTraceFrame {
+            kind,
+            from,
+            to: Some(inputs.bytecode_address),
+            value,
+            gas: inputs.gas_limit,
+            input: inputs.input.clone(),
+            ..Default::default()
+        });
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.pop(context, &outcome.result);
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.stack.push(CallTraceFrame {
+            kind: inputs.scheme.into(),
+            from: inputs.caller,
+            value: Some(inputs.value),
+            gas: inputs.gas_limit,
+            input: inputs.init_code.clone(),
+            ..Default::default()
+        });
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        if let Some(frame) = self.stack.last_mut() {
+            frame.to = outcome.address;
+        }
+        self.pop(context, &outcome.result);
+        outcome
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        if self.config.only_top_call {
+            return;
+        }
+        if let Some(frame) = self.stack.last_mut() {
+            frame.calls.push(CallTraceFrame {
+                kind: CallTraceKind::SelfDestruct,
+                from: contract,
+                to: Some(target),
+                value: Some(value),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// Gas used by the transaction including the intrinsic gas and the refund.
+pub(super) fn tx_gas_used<DB: Database>(
+    context: &EvmContext<DB>,
+    result: &InterpreterResult,
+) -> u64 {
+    let mut gas = result.gas;
+    if result.is_ok() {
+        gas.set_final_refund(SpecId::enabled(context.spec_id(), SpecId::LONDON));
+    } else {
+        gas.set_refund(0);
+    }
+    context
+        .env
+        .tx
+        .gas_limit
+        .saturating_sub(gas.remaining())
+        .saturating_sub(gas.refunded() as u64)
+}
+
+/// Returns the error message geth uses for the instruction result.
+pub(super) fn geth_error(result: InstructionResult) -> &'static str {
+    match result {
+        InstructionResult::Revert => "execution reverted",
+        InstructionResult::OutOfGas
+        | InstructionResult::MemoryOOG
+        | InstructionResult::MemoryLimitOOG
+        | InstructionResult::PrecompileOOG
+        | InstructionResult::InvalidOperandOOG => "out of gas",
+        InstructionResult::OpcodeNotFound
+        | InstructionResult::InvalidFEOpcode
+        | InstructionResult::NotActivated => "invalid opcode",
+        InstructionResult::InvalidJump => "invalid jump destination",
+        InstructionResult::StackUnderflow => "stack underflow",
+        InstructionResult::StackOverflow => "stack limit reached 1024",
+        InstructionResult::OutOfOffset => "return data out of bounds",
+        InstructionResult::CallTooDeep => "max call depth exceeded",
+        InstructionResult::OutOfFunds => "insufficient balance for transfer",
+        InstructionResult::CreateCollision => "contract address collision",
+        InstructionResult::CallNotAllowedInsideStatic
+        | InstructionResult::StateChangeDuringStaticCall => "write protection",
+        InstructionResult::CreateContractSizeLimit => "max code size exceeded",
+        InstructionResult::CreateContractStartingWithEF => "invalid code: must not begin with 0xef",
+        InstructionResult::CreateInitCodeSizeLimit => "max initcode size exceeded",
+        InstructionResult::NonceOverflow => "nonce uint64 overflow",
+        InstructionResult::OverflowPayment => "gas uint64 overflow",
+        InstructionResult::PrecompileError => "precompile failed",
+        _ => "execution failed",
+    }
+}
+
+/// Decodes the `Error(string)` revert reason of the output.
+fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    /// Selector of `Error(string)`.
+    const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+    let data = output.strip_prefix(&ERROR_SELECTOR)?;
+    let word = |offset: usize| -> Option<usize> {
+        let word = data.get(offset..offset.checked_add(32)?)?;
+        U256::from_be_slice(word).try_into().ok()
+    };
+    let offset = word(0)?;
+    let len = word(offset)?;
+    let start = offset.checked_add(32)?;
+    let reason = data.get(start..start.checked_add(len)?)?;
+    String::from_utf8(reason.to_vec()).ok()
+}
+
+#[cfg(feature = "serde")]
+mod serde_hex_u64 {
+    use crate::primitives::alloy_primitives::U64;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        U64::from(*value).serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        U64::deserialize(deserializer).map(|value| value.to())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        inspector_handle_register,
+        interpreter::opcode,
+        primitives::{address, bytes, AccountInfo, Bytecode, TxKind},
+        test_utils::{test_evm_builder, TEST_CALLER, TEST_CONTRACT},
+    };
+
+    #[test]
+    fn test_call_tracer() {
+        let logger = address!("3000000000000000000000000000000000000000");
+
+        let mut db = CacheDB::new(EmptyDB::default());
+        // LOG0 then CALL logger, then LOG0 again.
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from(vec![
+                opcode::PUSH1,
+                0,
+                opcode::PUSH1,
+                0,
+                opcode::LOG0,
+                opcode::PUSH1,
+                0,
+                opcode::PUSH1,
+                0,
+                opcode::PUSH1,
+                0,
+                opcode::PUSH1,
+                0,
+                opcode::PUSH1,
+                0,
+                opcode::PUSH20,
+                0x30,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                opcode::GAS,
+                opcode::CALL,
+                opcode::POP,
+                opcode::PUSH1,
+                0,
+                opcode::PUSH1,
+                0,
+                opcode::LOG0,
+                opcode::STOP,
+            ]))),
+        );
+        // LOG0 then revert, the log is dropped.
+        db.insert_account_info(
+            logger,
+            AccountInfo::from_bytecode(Bytecode::new_raw(bytes!("60006000a060006000fd"))),
+        );
+
+        let mut evm = test_evm_builder(db, TxKind::Call(TEST_CONTRACT))
+            .with_external_context(CallTracer::new(CallTracerConfig {
+                only_top_call: false,
+                with_log: true,
+            }))
+            .modify_tx_env(|tx| tx.data = bytes!("01"))
+            .append_handler_register(inspector_handle_register)
+            .build();
+        let result = evm.transact().unwrap().result;
+
+        let frame = evm.context.external.take_frame().unwrap();
+        assert_eq!(frame.kind, CallTraceKind::Call);
+        assert_eq!((frame.from, frame.to), (TEST_CALLER, Some(TEST_CONTRACT)));
+        assert_eq!(frame.value, Some(U256::ZERO));
+        assert_eq!(frame.input, bytes!("01"));
+        assert_eq!(frame.gas, 100_000);
+        assert_eq!(frame.gas_used, result.gas_used());
+        assert_eq!(frame.error, None);
+        assert_eq!(
+            frame
+                .logs
+                .iter()
+                .map(|log| log.position)
+                .collect::<Vec<_>>(),
+            [0, 1]
+        );
+
+        assert_eq!(frame.calls.len(), 1);
+        let call = &frame.calls[0];
+        assert_eq!((call.from, call.to), (TEST_CONTRACT, Some(logger)));
+        assert_eq!(call.error.as_deref(), Some("execution reverted"));
+        assert!(call.logs.is_empty());
+        assert!(call.calls.is_empty());
+
+        // with `onlyTopCall` subcalls are not traced.
+        evm.context.external = CallTracer::new(CallTracerConfig {
+            only_top_call: true,
+            with_log: false,
+        });
+        evm.transact().unwrap();
+        let frame = evm.context.external.take_frame().unwrap();
+        assert!(frame.calls.is_empty());
+        assert!(frame.logs.is_empty());
+    }
+
+    #[test]
+    fn test_decode_revert_reason() {
+        let output = bytes!(
+            "08c379a0"
+            "0000000000000000000000000000000000000000000000000000000000000020"
+            "0000000000000000000000000000000000000000000000000000000000000005"
+            "68656c6c6f000000000000000000000000000000000000000000000000000000"
+        );
+        assert_eq!(decode_revert_reason(&output).as_deref(), Some("hello"));
+        assert_eq!(decode_revert_reason(&output[..40]), None);
+        assert_eq!(decode_revert_reason(&[]), None);
+    }
+
+    #[cfg(feature = "serde-json")]
+    #[test]
+    fn test_call_trace_frame_json() {
+        let frame = CallTraceFrame {
+            kind: CallTraceKind::StaticCall,
+            from: Address::ZERO,
+            to: Some(Address::ZERO),
+            gas: 0x100,
+            gas_used: 0x10,
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&frame).unwrap(),
+            serde_json::json!({
+                "type": "STATICCALL",
+                "from": "0x0000000000000000000000000000000000000000",
+                "to": "0x0000000000000000000000000000000000000000",
+                "gas": "0x100",
+                "gasUsed": "0x10",
+                "input": "0x",
+            })
+        );
+        let decoded: CallTraceFrame =
+            serde_json::from_value(serde_json::to_value(&frame).unwrap()).unwrap();
+        assert_eq!(decoded, frame);
+    }
+}
@@ -0,0 +1,399 @@
+//! Bytecode coverage [Inspector] with an lcov exporter.
+
+use crate::{
+    interpreter::{opcode, Interpreter},
+    primitives::{db::Database, keccak256, Bytes, HashMap, JumpTable, B256},
+    EvmContext, Inspector,
+};
+use core::fmt::Write;
+use std::{collections::BTreeMap, string::String, vec::Vec};
+
+/// Coverage of each executed bytecode, indexed by its code hash.
+pub type CoverageMap = BTreeMap<B256, CodeCoverage>;
+
+/// Number of times a `JUMPI` took each direction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BranchCoverage {
+    /// Number of times the jump was taken.
+    pub taken: u64,
+    /// Number of times execution continued with the next instruction.
+    pub not_taken: u64,
+}
+
+/// Coverage of a single bytecode.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodeCoverage {
+    /// Original bytecode.
+    pub code: Bytes,
+    /// Valid jump destinations of the bytecode.
+    pub jump_table: JumpTable,
+    /// Number of times each program counter was executed, indexed by the program counter.
+    pub hits: Vec<u64>,
+    /// Directions taken by each `JUMPI`, indexed by its program counter.
+    pub branches: BTreeMap<usize, BranchCoverage>,
+}
+
+impl CodeCoverage {
+    fn new(code: Bytes, jump_table: JumpTable) -> Self {
+        Self {
+            hits: vec![0; code.len()],
+            code,
+            jump_table,
+            branches: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the program counter of each instruction of the bytecode, skipping push data.
+    pub fn instructions(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut pc = 0;
+        core::iter::from_fn(move || {
+            let current = pc;
+            let opcode = *self.code.get(current)?;
+            pc += 1;
+            if (opcode::PUSH1..=opcode::PUSH32).contains(&opcode) {
+                pc += (opcode - opcode::PUSH0) as usize;
+            }
+            Some(current)
+        })
+    }
+
+    /// Returns the program counters that were executed.
+    pub fn executed(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hits
+            .iter()
+            .enumerate()
+            .filter(|(_, hits)| **hits != 0)
+            .map(|(pc, _)| pc)
+    }
+
+    /// Returns each valid jump destination and the number of times it was executed.
+    ///
+    /// Jump destinations are the start of the basic blocks that can be jumped to.
+    pub fn jumpdests(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.hits
+            .iter()
+            .enumerate()
+            .filter(|(pc, _)| self.jump_table.is_valid(*pc))
+            .map(|(pc, hits)| (pc, *hits))
+    }
+
+    /// Exports the coverage in the lcov format.
+    ///
+    /// Without a source map, a single record named `name` is written, its lines are the
+    /// program counters plus one and its functions are the jump destinations. With a source map,
+    /// a record is written for each source file that has instructions mapped to it.
+    pub fn to_lcov(&self, name: &str, source_map: Option<&SourceMap<'_>>) -> String {
+        let mut lcov = String::new();
+        match source_map {
+            Some(source_map) => self.write_lcov_with_source_map(&mut lcov, source_map),
+            None => {
+                let lines = self
+                    .instructions()
+                    .map(|pc| (pc + 1, self.hits[pc]))
+                    .collect();
+                let branches: Vec<_> = self
+                    .instructions()
+                    .filter(|pc| self.code[*pc] == opcode::JUMPI)
+                    .map(|pc| (pc + 1, pc))
+                    .collect();
+                let functions = self.jumpdests().map(|(pc, hits)| (pc + 1, hits)).collect();
+                self.write_lcov_record(&mut lcov, name, &lines, &branches, &functions);
+            }
+        }
+        lcov
+    }
+
+    fn write_lcov_with_source_map(&self, lcov: &mut String, source_map: &SourceMap<'_>) {
+        // lines and branches of each source file.
+        let mut files = BTreeMap::<usize, (BTreeMap<usize, u64>, Vec<(usize, usize)>)>::new();
+        for (pc, location) in self.instructions().zip(source_map.locations()) {
+            let Some((file, offset)) = location else {
+                continue;
+            };
+            let Some((_, content)) = source_map.sources.get(file) else {
+                continue;
+            };
+            let line = content
+                .as_bytes()
+                .iter()
+                .take(offset)
+                .filter(|byte| **byte == b'\n')
+                .count()
+                + 1;
+            let (lines, branches) = files.entry(file).or_default();
+            let hits = lines.entry(line).or_default();
+            *hits = (*hits).max(self.hits[pc]);
+            if self.code[pc] == opcode::JUMPI {
+                branches.push((line, pc));
+            }
+        }
+        for (file, (lines, branches)) in files {
+            let (path, _) = source_map.sources[file];
+            self.write_lcov_record(lcov, path, &lines, &branches, &BTreeMap::new());
+        }
+    }
+
+    /// Writes a record, `branches` are the lines and program counters of the `JUMPI`s.
+    fn write_lcov_record(
+        &self,
+        lcov: &mut String,
+        name: &str,
+        lines: &BTreeMap<usize, u64>,
+        branches: &[(usize, usize)],
+        functions: &BTreeMap<usize, u64>,
+    ) {
+        let _ = writeln!(lcov, "TN:\nSF:{name}");
+        for line in functions.keys() {
+            let _ = writeln!(lcov, "FN:{line},block_{}", line - 1);
+        }
+        for (line, hits) in functions {
+            let _ = writeln!(lcov, "FNDA:{hits},block_{}", line - 1);
+        }
+        if !functions.is_empty() {
+            let hit = functions.values().filter(|hits| **hits != 0).count();
+            let _ = writeln!(lcov, "FNF:{}\nFNH:{hit}", functions.len());
+        }
+        let mut branches_hit = 0;
+        for (line, pc) in branches {
+            let branch = self.branches.get(pc).copied().unwrap_or_default();
+            for (index, taken) in [branch.not_taken, branch.taken].into_iter().enumerate() {
+                if self.hits[*pc] == 0 {
+                    let _ = writeln!(lcov, "BRDA:{line},{pc},{index},-");
+                } else {
+                    let _ = writeln!(lcov, "BRDA:{line},{pc},{index},{taken}");
+                }
+                branches_hit += (taken != 0) as usize;
+            }
+        }
+        if !branches.is_empty() {
+            let _ = writeln!(lcov, "BRF:{}\nBRH:{branches_hit}", branches.len() * 2);
+        }
+        for (line, hits) in lines {
+            let _ = writeln!(lcov, "DA:{line},{hits}");
+        }
+        let hit = lines.values().filter(|hits| **hits != 0).count();
+        let _ = writeln!(lcov, "LF:{}\nLH:{hit}\nend_of_record", lines.len());
+    }
+}
+
+/// Solidity compiler source map of a bytecode.
+#[derive(Clone, Copy, Debug)]
+pub struct SourceMap<'a> {
+    /// Compressed source map, `s:l:f:j:m` entries separated by `;`, one per instruction.
+    pub map: &'a str,
+    /// Path and content of the source files, indexed by the source index of the map.
+    pub sources: &'a [(&'a str, &'a str)],
+}
+
+impl SourceMap<'_> {
+    /// Returns the source index and offset of each instruction, `None` if it is not mapped.
+    fn locations(&self) -> impl Iterator<Item = Option<(usize, usize)>> + '_ {
+        let mut offset = 0i64;
+        let mut file = -1i64;
+        self.map.split(';').map(move |entry| {
+            let mut fields = entry.split(':');
+            if let Some(Ok(value)) = fields.next().filter(|s| !s.is_empty()).map(str::parse) {
+                offset = value;
+            }
+            // length is not needed.
+            fields.next();
+            if let Some(Ok(value)) = fields.next().filter(|s| !s.is_empty()).map(str::parse) {
+                file = value;
+            }
+            Some((usize::try_from(file).ok()?, usize::try_from(offset).ok()?))
+        })
+    }
+}
+
+/// [Inspector] that records the executed program counters and the `JUMPI` directions of each
+/// legacy bytecode.
+///
+/// Coverage is indexed by code hash, so bytecode deployed at multiple addresses is merged. The
+/// initcode of `CREATE` frames has no code hash, it is indexed by the keccak hash of the initcode.
+/// Only a counter is incremented on each step, so it is cheap enough to run on whole test suites.
+#[derive(Clone, Debug, Default)]
+pub struct CoverageInspector {
+    coverage: Vec<(B256, CodeCoverage)>,
+    /// Index of the coverage of each code hash.
+    index: HashMap<B256, usize>,
+    /// Coverage index of the bytecode of each active frame, indexed by the call depth.
+    frames: Vec<usize>,
+}
+
+impl CoverageInspector {
+    /// Returns the coverage of the given code hash.
+    pub fn code_coverage(&self, code_hash: &B256) -> Option<&CodeCoverage> {
+        self.index
+            .get(code_hash)
+            .map(|index| &self.coverage[*index].1)
+    }
+
+    /// Returns the coverage of all executed bytecodes.
+    pub fn coverage(&self) -> CoverageMap {
+        self.coverage.iter().cloned().collect()
+    }
+
+    /// Consumes the inspector and returns the coverage of all executed bytecodes.
+    pub fn into_coverage(self) -> CoverageMap {
+        self.coverage.into_iter().collect()
+    }
+
+    /// Returns the coverage index of the bytecode being executed by the interpreter.
+    fn coverage_index(&mut self, interp: &Interpreter) -> usize {
+        let bytecode = &interp.contract.bytecode;
+        let code_hash = interp
+            .contract
+            .hash
+            // `CREATE` initcode has a zero hash, `CREATE2` the keccak hash of the initcode.
+            .filter(|hash| !hash.is_zero())
+            .unwrap_or_else(|| keccak256(bytecode.original_byte_slice()));
+        *self.index.entry(code_hash).or_insert_with(|| {
+            let jump_table = bytecode.legacy_jump_table().cloned().unwrap_or_default();
+            self.coverage.push((
+                code_hash,
+                CodeCoverage::new(bytecode.original_bytes(), jump_table),
+            ));
+            self.coverage.len() - 1
+        })
+    }
+}
+
+impl<DB: Database> Inspector<DB> for CoverageInspector {
+    fn initialize_interp(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        let index = self.coverage_index(interp);
+        let depth = context.journaled_state.depth;
+        self.frames.truncate(depth);
+        self.frames.resize(depth + 1, index);
+        self.frames[depth] = index;
+    }
+
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        let Some(&index) = self.frames.get(context.journaled_state.depth) else {
+            return;
+        };
+        let coverage = &mut self.coverage[index].1;
+        let pc = interp.program_counter();
+        // padding after the original bytecode is not covered.
+        let Some(hits) = coverage.hits.get_mut(pc) else {
+            return;
+        };
+        *hits += 1;
+        if interp.current_opcode() == opcode::JUMPI {
+            if let Ok(condition) = interp.stack.peek(1) {
+                let branch = coverage.branches.entry(pc).or_default();
+                if condition.is_zero() {
+                    branch.not_taken += 1;
+                } else {
+                    branch.taken += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        inspector_handle_register,
+        primitives::{AccountInfo, Bytecode, TxKind},
+        test_utils::{test_evm_builder, TEST_CONTRACT},
+    };
+
+    // JUMPI(6, CALLDATASIZE), STOP, JUMPDEST, STOP
+    const CODE: &[u8] = &[0x36, 0x60, 0x06, 0x57, 0x00, 0x00, 0x5b, 0x00];
+
+    fn coverage(calldata: &[&'static [u8]]) -> CodeCoverage {
+        let bytecode = Bytecode::new_raw(Bytes::from_static(CODE));
+        let code_hash = bytecode.hash_slow();
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(TEST_CONTRACT, AccountInfo::from_bytecode(bytecode));
+
+        let mut evm = test_evm_builder(db, TxKind::Call(TEST_CONTRACT))
+            .with_external_context(CoverageInspector::default())
+            .append_handler_register(inspector_handle_register)
+            .build();
+        for data in calldata {
+            evm.tx_mut().data = Bytes::from_static(data);
+            assert!(evm.transact().unwrap().result.is_success());
+        }
+        evm.context
+            .external
+            .code_coverage(&code_hash)
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn test_coverage() {
+        let coverage = coverage(&[&[], &[1], &[1]]);
+        assert_eq!(coverage.hits, vec![3, 3, 0, 3, 1, 0, 2, 2]);
+        assert_eq!(
+            coverage.executed().collect::<Vec<_>>(),
+            vec![0, 1, 3, 4, 6, 7]
+        );
+        assert_eq!(
+            coverage.instructions().collect::<Vec<_>>(),
+            vec![0, 1, 3, 4, 5, 6, 7]
+        );
+        assert_eq!(coverage.jumpdests().collect::<Vec<_>>(), vec![(6, 2)]);
+        assert_eq!(
+            coverage.branches[&3],
+            BranchCoverage {
+                taken: 2,
+                not_taken: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_initcode_coverage() {
+        let mut evm = test_evm_builder(CacheDB::new(EmptyDB::default()), TxKind::Create)
+            .with_external_context(CoverageInspector::default())
+            .modify_tx_env(|tx| tx.data = Bytes::from_static(CODE))
+            .append_handler_register(inspector_handle_register)
+            .build();
+        assert!(evm.transact().unwrap().result.is_success());
+
+        // initcode has no calldata, so the jump is not taken.
+        let coverage = evm
+            .context
+            .external
+            .code_coverage(&keccak256(CODE))
+            .unwrap();
+        assert_eq!(coverage.hits, vec![1, 1, 0, 1, 1, 0, 0, 0]);
+        assert_eq!(
+            coverage.branches[&3],
+            BranchCoverage {
+                taken: 0,
+                not_taken: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_lcov() {
+        let coverage = coverage(&[&[1]]);
+        assert_eq!(
+            coverage.to_lcov("contract", None),
+            "TN:\nSF:contract\nFN:7,block_6\nFNDA:1,block_6\nFNF:1\nFNH:1\n\
+             BRDA:4,3,0,0\nBRDA:4,3,1,1\nBRF:2\nBRH:1\n\
+             DA:1,1\nDA:2,1\nDA:4,1\nDA:5,0\nDA:6,0\nDA:7,1\nDA:8,1\nLF:7\nLH:5\nend_of_record\n"
+        );
+
+        // instructions 0-3 are on line 1, the rest on line 2.
+        let source = "if (msg.data.length > 0)\nreturn;";
+        let source_map = SourceMap {
+            map: "0:24:0:-;;;;25:7;;",
+            sources: &[("Contract.sol", source)],
+        };
+        assert_eq!(
+            coverage.to_lcov("contract", Some(&source_map)),
+            "TN:\nSF:Contract.sol\nBRDA:1,3,0,0\nBRDA:1,3,1,1\nBRF:2\nBRH:1\n\
+             DA:1,1\nDA:2,1\nLF:2\nLH:2\nend_of_record\n"
+        );
+    }
+}
@@ -0,0 +1,349 @@
+//! Gas profiler [Inspector], used to render flamegraphs of transactions.
+
+use crate::{
+    inspectors::GasInspector,
+    interpreter::{
+        gas::{memory_gas_for_len, CALL_STIPEND},
+        opcode::OpCode,
+        CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome, Interpreter,
+    },
+    primitives::{db::Database, hex},
+    EvmContext, Inspector,
+};
+use std::{collections::BTreeMap, format, string::String, vec::Vec};
+
+/// Configuration of the [GasProfiler].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GasProfilerConfig {
+    /// Attributes the gas of each frame to its opcodes.
+    pub per_opcode: bool,
+}
+
+/// Frame of the call stack that is being profiled.
+#[derive(Debug)]
+struct ProfilerFrame {
+    /// Folded stack of the frame, frame names separated by `;`.
+    stack: String,
+    /// Gas spent by the child frames.
+    children_gas: u64,
+    /// Gas spent on memory expansion.
+    memory_gas: u64,
+    /// Gas spent by each opcode, excluding memory expansion and gas passed to child frames.
+    opcode_gas: BTreeMap<&'static str, u64>,
+    /// Opcode that is being executed.
+    opcode: u8,
+    /// Memory size before the opcode is executed.
+    memory_len: usize,
+    /// Time the frame started.
+    #[cfg(feature = "std")]
+    start: std::time::Instant,
+    /// Time spent in the child frames.
+    #[cfg(feature = "std")]
+    children_time: std::time::Duration,
+}
+
+/// [Inspector] that attributes gas and time to call stacks and outputs them in the folded
+/// stack format used by flamegraph tools.
+///
+/// Frames are named `address:selector` for calls and `address:create` for creates, with the
+/// code address for delegate calls. Calls to precompiles are frames named
+/// `precompile:address`, and memory expansion costs are `memory` frames under the frame that
+/// expanded its memory. With [GasProfilerConfig::per_opcode] enabled the gas spent by each frame
+/// is split into frames named by the opcodes.
+#[derive(Debug, Default)]
+pub struct GasProfiler {
+    config: GasProfilerConfig,
+    gas_inspector: GasInspector,
+    /// Frames that are being executed.
+    stack: Vec<ProfilerFrame>,
+    /// Gas spent by each folded stack.
+    gas: BTreeMap<String, u64>,
+    /// Time spent by each folded stack, in nanoseconds.
+    #[cfg(feature = "std")]
+    time: BTreeMap<String, u64>,
+}
+
+impl GasProfiler {
+    /// Creates a new profiler with the given configuration.
+    pub fn new(config: GasProfilerConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the gas spent by each folded stack.
+    pub fn gas(&self) -> &BTreeMap<String, u64> {
+        &self.gas
+    }
+
+    /// Returns the gas in the folded stack format, one `stack gas` line per stack.
+    pub fn folded_gas(&self) -> String {
+        folded(&self.gas)
+    }
+
+    /// Returns the time in the folded stack format, one `stack nanoseconds` line per stack.
+    #[cfg(feature = "std")]
+    pub fn folded_time(&self) -> String {
+        folded(&self.time)
+    }
+
+    /// Clears the collected profile.
+    pub fn clear(&mut self) {
+        self.stack.clear();
+        self.gas.clear();
+        #[cfg(feature = "std")]
+        self.time.clear();
+    }
+
+    fn push_frame(&mut self, name: String) {
+        let stack = match self.stack.last() {
+            Some(parent) => format!("{};{name}", parent.stack),
+            None => name,
+        };
+        self.stack.push(ProfilerFrame {
+            stack,
+            children_gas: 0,
+            memory_gas: 0,
+            opcode_gas: BTreeMap::new(),
+            opcode: 0,
+            memory_len: 0,
+            #[cfg(feature = "std")]
+            start: std::time::Instant::now(),
+            #[cfg(feature = "std")]
+            children_time: std::time::Duration::ZERO,
+        });
+    }
+
+    /// Pops the current frame and records the gas it spent.
+    fn pop_frame(&mut self, gas_spent: u64) {
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+        let mut self_gas = gas_spent.saturating_sub(frame.children_gas);
+        if frame.memory_gas != 0 {
+            self_gas = self_gas.saturating_sub(frame.memory_gas);
+            self.record_gas(format!("{};memory", frame.stack), frame.memory_gas);
+        }
+        if self.config.per_opcode {
+            for (name, gas) in &frame.opcode_gas {
+                self_gas = self_gas.saturating_sub(*gas);
+                self.record_gas(format!("{};{name}", frame.stack), *gas);
+            }
+        }
+        #[cfg(feature = "std")]
+        let elapsed = frame.start.elapsed();
+        #[cfg(feature = "std")]
+        {
+            let self_time = elapsed.saturating_sub(frame.children_time);
+            *self.time.entry(frame.stack.clone()).or_default() += self_time.as_nanos() as u64;
+        }
+        self.record_gas(frame.stack, self_gas);
+
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children_gas += gas_spent;
+            #[cfg(feature = "std")]
+            {
+                parent.children_time += elapsed;
+            }
+        }
+    }
+
+    fn record_gas(&mut self, stack: String, gas: u64) {
+        if gas != 0 {
+            *self.gas.entry(stack).or_default() += gas;
+        }
+    }
+
+    /// Removes the gas passed to a child frame from the opcode that created it.
+    fn remove_child_gas(&mut self, gas: u64) {
+        if let Some(frame) = self.stack.last_mut() {
+            if let Some(name) = OpCode::new(frame.opcode).map(OpCode::as_str) {
+                if let Some(opcode_gas) = frame.opcode_gas.get_mut(name) {
+                    *opcode_gas = opcode_gas.saturating_sub(gas);
+                }
+            }
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for GasProfiler {
+    fn initialize_interp(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.gas_inspector.initialize_interp(interp, context);
+    }
+
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.gas_inspector.step(interp, context);
+        if let Some(frame) = self.stack.last_mut() {
+            frame.opcode = interp.current_opcode();
+            frame.memory_len = interp.shared_memory.len();
+        }
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.gas_inspector.step_end(interp, context);
+        let per_opcode = self.config.per_opcode;
+        let Some(frame) = self.stack.last_mut() else {
+            return;
+        };
+        let memory_len = interp.shared_memory.len();
+        let memory_gas = if memory_len > frame.memory_len {
+            memory_gas_for_len(memory_len) - memory_gas_for_len(frame.memory_len)
+        } else {
+            0
+        };
+        frame.memory_gas += memory_gas;
+        if per_opcode {
+            if let Some(name) = OpCode::new(frame.opcode).map(OpCode::as_str) {
+                let gas = self
+                    .gas_inspector
+                    .last_gas_cost()
+                    .saturating_sub(memory_gas);
+                *frame.opcode_gas.entry(name).or_default() += gas;
+            }
+        }
+    }
+
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        // the stipend is not paid by the caller.
+        let stipend = if inputs.transfers_value()
+            && matches!(inputs.scheme, CallScheme::Call | CallScheme::CallCode)
+        {
+            CALL_STIPEND
+        } else {
+            0
+        };
+        self.remove_child_gas(inputs.gas_limit.saturating_sub(stipend));
+
+        let name = if context.precompiles.contains(&inputs.bytecode_address) {
+            format!("precompile:{}", inputs.bytecode_address)
+        } else {
+            match inputs.input.get(..4) {
+                Some(selector) => {
+                    format!("{}:0x{}", inputs.bytecode_address, hex::encode(selector))
+                }
+                None => format!("{}:fallback", inputs.bytecode_address),
+            }
+        };
+        self.push_frame(name);
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        let outcome = self.gas_inspector.call_end(context, inputs, outcome);
+        self.pop_frame(outcome.result.gas.spent());
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.remove_child_gas(inputs.gas_limit);
+
+        // caller nonce is bumped when the frame is created.
+        let nonce = context
+            .journaled_state
+            .state
+            .get(&inputs.caller)
+            .map(|account| account.info.nonce)
+            .unwrap_or_default();
+        self.push_frame(format!("{}:create", inputs.created_address(nonce)));
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        let outcome = self.gas_inspector.create_end(context, inputs, outcome);
+        self.pop_frame(outcome.result.gas.spent());
+        outcome
+    }
+}
+
+/// Formats the weights in the folded stack format.
+fn folded(weights: &BTreeMap<String, u64>) -> String {
+    weights
+        .iter()
+        .map(|(stack, weight)| format!("{stack} {weight}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        inspector_handle_register,
+        primitives::{AccountInfo, Bytecode, Bytes, TxKind},
+        test_utils::{test_evm_builder, TEST_CONTRACT},
+    };
+
+    fn profile(config: GasProfilerConfig) -> GasProfiler {
+        let mut db = CacheDB::new(EmptyDB::default());
+        // MSTORE(0, 1), STATICCALL(GAS, 2, 0, 32, 0, 32)
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                0x60, 0x01, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0x60, 0x20, 0x60, 0x00, 0x60,
+                0x02, 0x5a, 0xfa, 0x00,
+            ]))),
+        );
+
+        let mut evm = test_evm_builder(db, TxKind::Call(TEST_CONTRACT))
+            .with_external_context(GasProfiler::new(config))
+            .modify_tx_env(|tx| tx.data = Bytes::from_static(&[0x12, 0x34, 0x56, 0x78]))
+            .append_handler_register(inspector_handle_register)
+            .build();
+        let result = evm.transact().unwrap().result;
+        assert!(result.is_success());
+
+        // all gas after the intrinsic gas is attributed.
+        let profiler = evm.context.external;
+        let intrinsic = 21_000 + 4 * 16;
+        assert_eq!(
+            profiler.gas().values().sum::<u64>(),
+            result.gas_used() - intrinsic
+        );
+        profiler
+    }
+
+    #[test]
+    fn test_gas_profiler() {
+        let profiler = profile(GasProfilerConfig::default());
+        let root = "0x2000000000000000000000000000000000000000:0x12345678";
+        let precompile = "precompile:0x0000000000000000000000000000000000000002";
+        assert_eq!(
+            profiler.folded_gas(),
+            format!(
+                "{root} {}\n{root};memory 3\n{root};{precompile} 72\n",
+                // PUSH1 * 7, MSTORE, GAS, warm STATICCALL
+                7 * 3 + 3 + 2 + 100
+            )
+        );
+    }
+
+    #[test]
+    fn test_gas_profiler_per_opcode() {
+        let profiler = profile(GasProfilerConfig { per_opcode: true });
+        let root = "0x2000000000000000000000000000000000000000:0x12345678";
+        assert_eq!(profiler.gas()[&format!("{root};PUSH1")], 7 * 3);
+        assert_eq!(profiler.gas()[&format!("{root};MSTORE")], 3);
+        assert_eq!(profiler.gas()[&format!("{root};STATICCALL")], 100);
+        assert_eq!(profiler.gas()[&format!("{root};memory")], 3);
+        assert!(!profiler.gas().contains_key(root));
+    }
+}
@@ -0,0 +1,343 @@
+//! [Inspector] that combines several inspectors, e.g. to trace calls and gas at the same time.
+
+use crate::{
+    interpreter::{
+        CallInputs, CallOutcome, CreateInputs, CreateOutcome, EOFCreateInputs, InstructionResult,
+        Interpreter,
+    },
+    primitives::{db::Database, Address, Log, U256},
+    EvmContext, Inspector,
+};
+use core::{any::Any, fmt};
+use std::{boxed::Box, vec::Vec};
+
+/// [Inspector] that can be downcast to its concrete type.
+trait AnyInspector<DB: Database>: Inspector<DB> {
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<DB: Database, T: Inspector<DB> + 'static> AnyInspector<DB> for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// [Inspector] that forwards every hook to a list of inspectors, in the order they were added.
+///
+/// Hooks that can change the execution have the following precedence:
+/// - `call`, `create` and `eofcreate` are forwarded to all inspectors, each one sees the inputs
+///   modified by the previous ones. The first returned outcome is used, the other ones are
+///   ignored.
+/// - `call_end`, `create_end` and `eofcreate_end` are forwarded to all inspectors, each one
+///   receives the outcome returned by the previous one.
+/// - `initialize_interp` and `step` stop at the first inspector that sets the instruction result
+///   to anything other than [InstructionResult::Continue], as the instruction is not executed.
+///   `step_end` is only forwarded to the inspectors whose `step` was called.
+///
+/// Inspectors can be retrieved by their type with [MultiInspector::get] after execution.
+pub struct MultiInspector<DB: Database> {
+    inspectors: Vec<Box<dyn AnyInspector<DB>>>,
+    /// Number of inspectors whose `step` was called for the current instruction.
+    stepped: usize,
+}
+
+impl<DB: Database> Default for MultiInspector<DB> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<DB: Database> fmt::Debug for MultiInspector<DB> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiInspector")
+            .field("len", &self.inspectors.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<DB: Database> MultiInspector<DB> {
+    /// Creates a new empty inspector.
+    pub fn new() -> Self {
+        Self {
+            inspectors: Vec::new(),
+            stepped: 0,
+        }
+    }
+
+    /// Adds an inspector and returns `self`.
+    pub fn with<T: Inspector<DB> + 'static>(mut self, inspector: T) -> Self {
+        self.push(inspector);
+        self
+    }
+
+    /// Adds an inspector after the existing ones.
+    pub fn push<T: Inspector<DB> + 'static>(&mut self, inspector: T) {
+        self.inspectors.push(Box::new(inspector));
+    }
+
+    /// Returns the number of inspectors.
+    pub fn len(&self) -> usize {
+        self.inspectors.len()
+    }
+
+    /// Returns `true` if there are no inspectors.
+    pub fn is_empty(&self) -> bool {
+        self.inspectors.is_empty()
+    }
+
+    /// Returns the first inspector of type `T`.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.inspectors
+            .iter()
+            .find_map(|inspector| (**inspector).as_any().downcast_ref())
+    }
+
+    /// Returns the first inspector of type `T` as mutable.
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.inspectors
+            .iter_mut()
+            .find_map(|inspector| (**inspector).as_any_mut().downcast_mut())
+    }
+}
+
+impl<DB: Database> Inspector<DB> for MultiInspector<DB> {
+    fn initialize_interp(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        for inspector in &mut self.inspectors {
+            inspector.initialize_interp(interp, context);
+            if interp.instruction_result != InstructionResult::Continue {
+                break;
+            }
+        }
+    }
+
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.stepped = 0;
+        for inspector in &mut self.inspectors {
+            inspector.step(interp, context);
+            self.stepped += 1;
+            if interp.instruction_result != InstructionResult::Continue {
+                break;
+            }
+        }
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        for inspector in &mut self.inspectors[..self.stepped] {
+            inspector.step_end(interp, context);
+        }
+    }
+
+    fn log(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>, log: &Log) {
+        for inspector in &mut self.inspectors {
+            inspector.log(interp, context, log);
+        }
+    }
+
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        let mut outcome = None;
+        for inspector in &mut self.inspectors {
+            let result = inspector.call(context, inputs);
+            outcome = outcome.or(result);
+        }
+        outcome
+    }
+
+    fn call_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &CallInputs,
+        mut outcome: CallOutcome,
+    ) -> CallOutcome {
+        for inspector in &mut self.inspectors {
+            outcome = inspector.call_end(context, inputs, outcome);
+        }
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        let mut outcome = None;
+        for inspector in &mut self.inspectors {
+            let result = inspector.create(context, inputs);
+            outcome = outcome.or(result);
+        }
+        outcome
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &CreateInputs,
+        mut outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        for inspector in &mut self.inspectors {
+            outcome = inspector.create_end(context, inputs, outcome);
+        }
+        outcome
+    }
+
+    fn eofcreate(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut EOFCreateInputs,
+    ) -> Option<CreateOutcome> {
+        let mut outcome = None;
+        for inspector in &mut self.inspectors {
+            let result = inspector.eofcreate(context, inputs);
+            outcome = outcome.or(result);
+        }
+        outcome
+    }
+
+    fn eofcreate_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &EOFCreateInputs,
+        mut outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        for inspector in &mut self.inspectors {
+            outcome = inspector.eofcreate_end(context, inputs, outcome);
+        }
+        outcome
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        for inspector in &mut self.inspectors {
+            inspector.selfdestruct(contract, target, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        inspector_handle_register,
+        inspectors::{CallTracer, CallTracerConfig, GasInspector},
+        interpreter::{Gas, InterpreterResult},
+        primitives::{AccountInfo, Bytecode, Bytes, TxKind},
+        test_utils::{test_evm_builder, TEST_CONTRACT},
+        Evm,
+    };
+
+    /// Returns a reverted outcome for every call.
+    #[derive(Default)]
+    struct RevertCalls {
+        calls: usize,
+    }
+
+    impl<DB: Database> Inspector<DB> for RevertCalls {
+        fn call(
+            &mut self,
+            _context: &mut EvmContext<DB>,
+            inputs: &mut CallInputs,
+        ) -> Option<CallOutcome> {
+            self.calls += 1;
+            Some(CallOutcome::new(
+                InterpreterResult::new(
+                    InstructionResult::Revert,
+                    Bytes::new(),
+                    Gas::new(inputs.gas_limit),
+                ),
+                inputs.return_memory_offset.clone(),
+            ))
+        }
+    }
+
+    /// Counts the steps, halting in `step` if `HALT` is set.
+    #[derive(Default)]
+    struct CountSteps<const HALT: bool> {
+        step: usize,
+        step_end: usize,
+    }
+
+    impl<DB: Database, const HALT: bool> Inspector<DB> for CountSteps<HALT> {
+        fn step(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+            self.step += 1;
+            if HALT {
+                interp.instruction_result = InstructionResult::Stop;
+            }
+        }
+
+        fn step_end(&mut self, _interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+            self.step_end += 1;
+        }
+    }
+
+    fn evm<EXT: Inspector<CacheDB<EmptyDB>>>(external: EXT) -> Evm<'static, EXT, CacheDB<EmptyDB>> {
+        let mut db = CacheDB::new(EmptyDB::default());
+        // SSTORE(0, 1)
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                0x60, 0x01, 0x60, 0x00, 0x55, 0x00,
+            ]))),
+        );
+        test_evm_builder(db, TxKind::Call(TEST_CONTRACT))
+            .with_external_context(external)
+            .append_handler_register(inspector_handle_register)
+            .build()
+    }
+
+    #[test]
+    fn test_multi_inspector() {
+        let inspector = MultiInspector::<CacheDB<EmptyDB>>::new()
+            .with(GasInspector::default())
+            .with(CallTracer::new(CallTracerConfig::default()));
+        let mut evm = evm(inspector);
+        assert!(evm.transact().unwrap().result.is_success());
+
+        let inspector = &evm.context.external;
+        assert_eq!(inspector.len(), 2);
+        assert!(inspector.get::<GasInspector>().unwrap().gas_remaining() > 0);
+        let frame = inspector.get::<CallTracer>().unwrap().frame().unwrap();
+        assert!(frame.error.is_none());
+        assert!(inspector.get::<RevertCalls>().is_none());
+    }
+
+    #[test]
+    fn test_multi_inspector_call_outcome() {
+        let inspector = MultiInspector::<CacheDB<EmptyDB>>::new()
+            .with(RevertCalls::default())
+            .with(CallTracer::new(CallTracerConfig::default()));
+        let mut evm = evm(inspector);
+        assert!(!evm.transact().unwrap().result.is_success());
+
+        // the first outcome is used and the following inspectors are still called.
+        let inspector = &evm.context.external;
+        assert_eq!(inspector.get::<RevertCalls>().unwrap().calls, 1);
+        let frame = inspector.get::<CallTracer>().unwrap().frame().unwrap();
+        assert_eq!(frame.error.as_deref(), Some("execution reverted"));
+    }
+
+    #[test]
+    fn test_multi_inspector_step_halt() {
+        let mut inspector = MultiInspector::<EmptyDB>::new()
+            .with(CountSteps::<true>::default())
+            .with(CountSteps::<false>::default());
+        let mut interp = Interpreter::new(Default::default(), u64::MAX, false);
+        let mut context = EvmContext::new(EmptyDB::default());
+        inspector.step(&mut interp, &mut context);
+        inspector.step_end(&mut interp, &mut context);
+
+        // only the inspectors whose step was called get step_end.
+        let halt = inspector.get::<CountSteps<true>>().unwrap();
+        assert_eq!((halt.step, halt.step_end), (1, 1));
+        let next = inspector.get::<CountSteps<false>>().unwrap();
+        assert_eq!((next.step, next.step_end), (0, 0));
+    }
+}
@@ -0,0 +1,412 @@
+//! Geth `prestateTracer` compatible tracer.
+
+use crate::{
+    db::DatabaseRef,
+    primitives::{Account, AccountInfo, Address, Bytes, EvmState, B256, KECCAK_EMPTY, U256},
+};
+use std::collections::BTreeMap;
+
+/// Configuration of the [PrestateTracer], same as the geth `prestateTracer` options.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct PrestateTracerConfig {
+    /// Report pre and post state of the changed fields only.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub diff_mode: bool,
+}
+
+/// Account state reported by the [PrestateTracer].
+///
+/// In diff mode the post state only contains the fields that changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct PrestateAccount {
+    /// Balance of the account.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub balance: Option<U256>,
+    /// Nonce of the account.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub nonce: Option<u64>,
+    /// Code of the account, `None` if it is empty.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub code: Option<Bytes>,
+    /// Size of the code.
+    #[cfg(feature = "scroll")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub code_size: Option<usize>,
+    /// Poseidon hash of the code.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub poseidon_code_hash: Option<B256>,
+    /// Accessed storage slots.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub storage: BTreeMap<B256, B256>,
+}
+
+impl PrestateAccount {
+    /// Sets the code and the code derived fields of the account.
+    #[cfg_attr(not(feature = "scroll"), allow(unused_variables))]
+    fn set_code(&mut self, info: &AccountInfo, code: Bytes) {
+        if code.is_empty() {
+            return;
+        }
+        self.code = Some(code);
+        #[cfg(feature = "scroll")]
+        {
+            self.code_size = Some(info.code_size);
+        }
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        if !info.poseidon_code_hash.is_zero() {
+            self.poseidon_code_hash = Some(info.poseidon_code_hash);
+        }
+    }
+
+    /// Returns `true` if no field is set.
+    fn is_empty(&self) -> bool {
+        self.balance.is_none()
+            && self.nonce.is_none()
+            && self.code.is_none()
+            && self.storage.is_empty()
+    }
+}
+
+/// Pre and post state of the accounts changed by the transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrestateDiff {
+    /// State of the changed accounts before the transaction.
+    pub pre: BTreeMap<Address, PrestateAccount>,
+    /// Changed fields of the accounts after the transaction.
+    pub post: BTreeMap<Address, PrestateAccount>,
+}
+
+/// Output of the [PrestateTracer], serializes to the geth `prestateTracer` format.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum PrestateFrame {
+    /// Pre state of every account and storage slot accessed by the transaction.
+    Default(BTreeMap<Address, PrestateAccount>),
+    /// Pre and post state of the changed fields, see [PrestateTracerConfig::diff_mode].
+    Diff(PrestateDiff),
+}
+
+/// Geth `prestateTracer` that works on the state returned by the transaction.
+///
+/// The pre state is read from the database the transaction was executed on, so
+/// [PrestateTracer::trace] needs to be called before the state is committed.
+///
+/// Storage of accounts created by the transaction is considered cleared, pre state of
+/// those slots is read from the database instead of the journaled original values.
+/// Slots of such accounts that were not accessed by the transaction are not reported.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PrestateTracer {
+    config: PrestateTracerConfig,
+}
+
+impl PrestateTracer {
+    /// Creates a tracer with the given config.
+    pub fn new(config: PrestateTracerConfig) -> Self {
+        Self { config }
+    }
+
+    /// Builds the prestate trace of the transaction from its resulting state.
+    pub fn trace<DB: DatabaseRef>(
+        &self,
+        db: &DB,
+        state: &EvmState,
+    ) -> Result<PrestateFrame, DB::Error> {
+        if self.config.diff_mode {
+            self.diff(db, state).map(PrestateFrame::Diff)
+        } else {
+            self.prestate(db, state).map(PrestateFrame::Default)
+        }
+    }
+
+    fn prestate<DB: DatabaseRef>(
+        &self,
+        db: &DB,
+        state: &EvmState,
+    ) -> Result<BTreeMap<Address, PrestateAccount>, DB::Error> {
+        let mut pre = BTreeMap::new();
+        for (address, account) in state {
+            let info = db.basic_ref(*address)?.unwrap_or_default();
+            let mut prestate = PrestateAccount {
+                balance: Some(info.balance),
+                nonce: (info.nonce != 0).then_some(info.nonce),
+                ..Default::default()
+            };
+            prestate.set_code(&info, original_code(db, &info)?);
+            for (slot, value) in original_storage(db, address, account)? {
+                prestate.storage.insert(slot.into(), value.into());
+            }
+            pre.insert(*address, prestate);
+        }
+        Ok(pre)
+    }
+
+    fn diff<DB: DatabaseRef>(&self, db: &DB, state: &EvmState) -> Result<PrestateDiff, DB::Error> {
+        let mut diff = PrestateDiff::default();
+        for (address, account) in state {
+            if !account.is_touched() {
+                continue;
+            }
+            let pre_info = db.basic_ref(*address)?;
+            let pre_exists = pre_info.as_ref().is_some_and(|info| info.exists());
+            // self destructed and touched empty accounts are removed from the state.
+            let post_exists = !account.is_selfdestructed() && !account.is_empty();
+            if !pre_exists && !post_exists {
+                continue;
+            }
+            let pre_info = pre_info.unwrap_or_default();
+
+            let mut pre = PrestateAccount {
+                balance: Some(pre_info.balance),
+                nonce: (pre_info.nonce != 0).then_some(pre_info.nonce),
+                ..Default::default()
+            };
+            pre.set_code(&pre_info, original_code(db, &pre_info)?);
+
+            let mut post = PrestateAccount::default();
+            let info = &account.info;
+            if info.balance != pre_info.balance {
+                post.balance = Some(info.balance);
+            }
+            if info.nonce != pre_info.nonce {
+                post.nonce = Some(info.nonce);
+            }
+            if info.code_hash != pre_info.code_hash {
+                let code = info
+                    .code
+                    .as_ref()
+                    .map(|code| code.original_bytes())
+                    .unwrap_or_default();
+                post.set_code(info, code);
+            }
+            for (slot, value) in original_storage(db, address, account)? {
+                let present = account.storage[&slot].present_value();
+                if present == value {
+                    continue;
+                }
+                pre.storage.insert(slot.into(), value.into());
+                if !present.is_zero() {
+                    post.storage.insert(slot.into(), present.into());
+                }
+            }
+
+            let modified = !post.is_empty() || !pre.storage.is_empty();
+            if pre_exists && (modified || !post_exists) {
+                diff.pre.insert(*address, pre);
+            }
+            if post_exists && modified {
+                diff.post.insert(*address, post);
+            }
+        }
+        Ok(diff)
+    }
+}
+
+/// Returns the code of the account before the transaction.
+fn original_code<DB: DatabaseRef>(db: &DB, info: &AccountInfo) -> Result<Bytes, DB::Error> {
+    if info.code_hash == KECCAK_EMPTY || info.code_hash.is_zero() {
+        return Ok(Bytes::new());
+    }
+    match &info.code {
+        Some(code) => Ok(code.original_bytes()),
+        None => Ok(db.code_by_hash_ref(info.code_hash)?.original_bytes()),
+    }
+}
+
+/// Returns the values of the accessed storage slots before the transaction.
+///
+/// Original values of accounts created by the transaction are zero, so they are read from the
+/// database.
+fn original_storage<DB: DatabaseRef>(
+    db: &DB,
+    address: &Address,
+    account: &Account,
+) -> Result<BTreeMap<U256, U256>, DB::Error> {
+    account
+        .storage
+        .iter()
+        .map(|(slot, value)| {
+            let original = if account.is_created() {
+                db.storage_ref(*address, *slot)?
+            } else {
+                value.original_value()
+            };
+            Ok((*slot, original))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        primitives::{address, AccountStatus, Bytecode, EvmStorageSlot},
+    };
+
+    const A: Address = address!("1000000000000000000000000000000000000000");
+    const B: Address = address!("2000000000000000000000000000000000000000");
+    const C: Address = address!("3000000000000000000000000000000000000000");
+    const D: Address = address!("4000000000000000000000000000000000000000");
+
+    fn slot(value: u64) -> B256 {
+        U256::from(value).into()
+    }
+
+    fn account(info: AccountInfo, status: AccountStatus) -> Account {
+        Account {
+            info,
+            storage: Default::default(),
+            status,
+        }
+    }
+
+    fn setup() -> (CacheDB<EmptyDB>, EvmState) {
+        let mut db = CacheDB::new(EmptyDB::default());
+        // A: balance and storage change.
+        db.insert_account_info(A, AccountInfo::from_balance(U256::from(10)));
+        db.insert_account_storage(A, U256::from(1), U256::from(5))
+            .unwrap();
+        // B: self destructed.
+        db.insert_account_info(B, AccountInfo::from_balance(U256::from(20)));
+        // D: read only.
+        db.insert_account_info(D, AccountInfo::from_balance(U256::from(30)));
+        // C: storage of the created account is cleared.
+        db.insert_account_storage(C, U256::from(2), U256::from(7))
+            .unwrap();
+
+        let mut a = account(
+            AccountInfo::from_balance(U256::from(9)),
+            AccountStatus::Touched,
+        );
+        a.storage.insert(
+            U256::from(1),
+            EvmStorageSlot::new_changed(U256::from(5), U256::ZERO),
+        );
+        a.storage
+            .insert(U256::from(3), EvmStorageSlot::new(U256::from(0)));
+        let b = account(
+            AccountInfo::default(),
+            AccountStatus::Touched | AccountStatus::SelfDestructed,
+        );
+        let code = Bytecode::new_raw(Bytes::from_static(&[0x00]));
+        let mut c = account(
+            AccountInfo {
+                nonce: 1,
+                ..AccountInfo::from_bytecode(code)
+            },
+            AccountStatus::Touched | AccountStatus::Created,
+        );
+        c.storage.insert(
+            U256::from(2),
+            EvmStorageSlot::new_changed(U256::ZERO, U256::from(8)),
+        );
+        let d = account(
+            AccountInfo::from_balance(U256::from(30)),
+            AccountStatus::empty(),
+        );
+        let state = [(A, a), (B, b), (C, c), (D, d)].into_iter().collect();
+        (db, state)
+    }
+
+    #[test]
+    fn test_prestate() {
+        let (db, state) = setup();
+        let PrestateFrame::Default(pre) = PrestateTracer::default().trace(&db, &state).unwrap()
+        else {
+            panic!("expected default mode");
+        };
+        assert_eq!(pre.len(), 4);
+        assert_eq!(pre[&A].balance, Some(U256::from(10)));
+        assert_eq!(
+            pre[&A].storage,
+            [(slot(1), slot(5)), (slot(3), slot(0))].into()
+        );
+        assert_eq!(pre[&C].balance, Some(U256::ZERO));
+        assert_eq!(pre[&C].code, None);
+        assert_eq!(pre[&C].storage, [(slot(2), slot(7))].into());
+        assert_eq!(pre[&D].balance, Some(U256::from(30)));
+    }
+
+    #[test]
+    fn test_prestate_diff() {
+        let (db, state) = setup();
+        let tracer = PrestateTracer::new(PrestateTracerConfig { diff_mode: true });
+        let PrestateFrame::Diff(diff) = tracer.trace(&db, &state).unwrap() else {
+            panic!("expected diff mode");
+        };
+
+        // A: only changed slot is reported, cleared slot is missing from post.
+        assert_eq!(
+            diff.pre[&A],
+            PrestateAccount {
+                balance: Some(U256::from(10)),
+                storage: [(slot(1), slot(5))].into(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            diff.post[&A],
+            PrestateAccount {
+                balance: Some(U256::from(9)),
+                ..Default::default()
+            }
+        );
+        // B: destroyed, present only in pre.
+        assert_eq!(diff.pre[&B].balance, Some(U256::from(20)));
+        assert!(!diff.post.contains_key(&B));
+        // C: did not exist before, pre value of the slot comes from the database.
+        assert!(!diff.pre.contains_key(&C));
+        assert_eq!(diff.post[&C].nonce, Some(1));
+        assert_eq!(diff.post[&C].code, Some(Bytes::from_static(&[0x00])));
+        #[cfg(feature = "scroll")]
+        assert_eq!(diff.post[&C].code_size, Some(1));
+        assert_eq!(diff.post[&C].storage, [(slot(2), slot(8))].into());
+        // D: not touched.
+        assert!(!diff.pre.contains_key(&D));
+        assert!(!diff.post.contains_key(&D));
+    }
+
+    #[test]
+    fn test_prestate_diff_created_and_destroyed() {
+        let db = CacheDB::new(EmptyDB::default());
+        let state = [(
+            A,
+            account(
+                AccountInfo::from_balance(U256::from(1)),
+                AccountStatus::Touched | AccountStatus::Created | AccountStatus::SelfDestructed,
+            ),
+        )]
+        .into_iter()
+        .collect();
+        let tracer = PrestateTracer::new(PrestateTracerConfig { diff_mode: true });
+        assert_eq!(
+            tracer.trace(&db, &state).unwrap(),
+            PrestateFrame::Diff(PrestateDiff::default())
+        );
+    }
+}
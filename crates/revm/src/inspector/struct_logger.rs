@@ -0,0 +1,386 @@
+//! Geth struct logger [Inspector], same output as the default `debug_traceTransaction` tracer.
+
+use super::call_tracer::{geth_error, tx_gas_used};
+use crate::{
+    inspectors::GasInspector,
+    interpreter::{
+        opcode, CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter,
+        InterpreterResult, OpCode,
+    },
+    primitives::{db::Database, hex, Address, HashMap, U256},
+    EvmContext, Inspector,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, io::Write};
+
+/// Configuration of the [StructLogger], same as the geth struct logger options.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StructLoggerConfig {
+    /// Includes the memory of each step.
+    pub enable_memory: bool,
+    /// Excludes the stack of each step.
+    pub disable_stack: bool,
+    /// Excludes the storage of `SLOAD` and `SSTORE` steps.
+    pub disable_storage: bool,
+    /// Includes the return data of each step.
+    pub enable_return_data: bool,
+    /// Maximum number of steps that are logged, zero for no limit.
+    pub limit: usize,
+}
+
+/// Log of a single step.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StructLog<'a> {
+    pc: usize,
+    op: &'static str,
+    gas: u64,
+    gas_cost: u64,
+    depth: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stack: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    return_data: Option<&'a str>,
+    #[serde(skip_serializing_if = "is_zero")]
+    refund: u64,
+}
+
+/// State of the interpreter before the step is executed.
+#[derive(Debug, Default)]
+struct Step {
+    pc: usize,
+    opcode: u8,
+    gas: u64,
+    refund: u64,
+    stack: Option<Vec<String>>,
+    memory: Option<Vec<String>>,
+    return_data: Option<String>,
+    /// Storage slot read by `SLOAD`.
+    sload: Option<U256>,
+}
+
+/// [Inspector] that writes the geth struct logger JSON of each transaction.
+///
+/// The output is `{"structLogs":[...],"gas":..,"failed":..,"returnValue":".."}` followed by a
+/// new line. Steps are written as soon as they are executed, so memory usage does not grow with
+/// the trace size.
+///
+/// Storage of `SLOAD` and `SSTORE` steps contains all the slots accessed by these opcodes in the
+/// current contract since the start of the transaction, same as geth.
+#[derive(Debug)]
+pub struct StructLogger<W> {
+    output: W,
+    config: StructLoggerConfig,
+    gas_inspector: GasInspector,
+    step: Step,
+    /// Storage accessed by `SLOAD` and `SSTORE`, by contract address.
+    storage: HashMap<Address, BTreeMap<U256, U256>>,
+    /// Number of logged steps.
+    steps: usize,
+}
+
+impl<W: Write> StructLogger<W> {
+    /// Creates a new logger writing to `output`.
+    pub fn new(config: StructLoggerConfig, output: W) -> Self {
+        Self {
+            output,
+            config,
+            gas_inspector: GasInspector::default(),
+            step: Step::default(),
+            storage: HashMap::new(),
+            steps: 0,
+        }
+    }
+
+    /// Returns the writer.
+    pub fn writer(&self) -> &W {
+        &self.output
+    }
+
+    /// Consumes the logger and returns the writer.
+    pub fn into_writer(self) -> W {
+        self.output
+    }
+
+    /// Returns `true` if the current step is logged.
+    fn is_logged(&self) -> bool {
+        self.config.limit == 0 || self.steps < self.config.limit
+    }
+
+    fn start(&mut self) {
+        self.gas_inspector = GasInspector::default();
+        self.storage.clear();
+        self.steps = 0;
+        let _ = self.output.write_all(b"{\"structLogs\":[");
+    }
+
+    fn finish<DB: Database>(&mut self, context: &EvmContext<DB>, result: &InterpreterResult) {
+        let _ = writeln!(
+            self.output,
+            "],\"gas\":{},\"failed\":{},\"returnValue\":\"{}\"}}",
+            tx_gas_used(context, result),
+            !result.is_ok(),
+            hex::encode(&result.output),
+        );
+        let _ = self.output.flush();
+    }
+}
+
+impl<DB: Database, W: Write> Inspector<DB> for StructLogger<W> {
+    fn initialize_interp(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.gas_inspector.initialize_interp(interp, context);
+    }
+
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.gas_inspector.step(interp, context);
+        if !self.is_logged() {
+            return;
+        }
+        let opcode = interp.current_opcode();
+        self.step = Step {
+            pc: interp.program_counter(),
+            opcode,
+            gas: interp.gas.remaining(),
+            refund: interp.gas.refunded() as u64,
+            stack: (!self.config.disable_stack).then(|| {
+                interp
+                    .stack
+                    .data()
+                    .iter()
+                    .map(|value| format!("{value:#x}"))
+                    .collect()
+            }),
+            memory: self.config.enable_memory.then(|| {
+                interp
+                    .shared_memory
+                    .context_memory()
+                    .chunks(32)
+                    .map(hex::encode)
+                    .collect()
+            }),
+            return_data: self
+                .config
+                .enable_return_data
+                .then(|| hex::encode_prefixed(&interp.return_data_buffer)),
+            sload: None,
+        };
+        if self.config.disable_storage {
+            return;
+        }
+        match opcode {
+            opcode::SLOAD => self.step.sload = interp.stack.peek(0).ok(),
+            opcode::SSTORE => {
+                if let (Ok(slot), Ok(value)) = (interp.stack.peek(0), interp.stack.peek(1)) {
+                    self.storage
+                        .entry(interp.contract.target_address)
+                        .or_default()
+                        .insert(slot, value);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.gas_inspector.step_end(interp, context);
+        if !self.is_logged() {
+            return;
+        }
+        self.steps += 1;
+
+        let step = core::mem::take(&mut self.step);
+        if let Some(slot) = step.sload {
+            // loaded value is on top of the stack.
+            if let Ok(value) = interp.stack.peek(0) {
+                self.storage
+                    .entry(interp.contract.target_address)
+                    .or_default()
+                    .insert(slot, value);
+            }
+        }
+        let storage = matches!(step.opcode, opcode::SLOAD | opcode::SSTORE)
+            .then(|| self.storage.get(&interp.contract.target_address))
+            .flatten()
+            .map(|storage| {
+                storage
+                    .iter()
+                    .map(|(slot, value)| {
+                        (
+                            hex::encode(slot.to_be_bytes::<32>()),
+                            hex::encode(value.to_be_bytes::<32>()),
+                        )
+                    })
+                    .collect()
+            });
+        let log = StructLog {
+            pc: step.pc,
+            op: OpCode::name_by_op(step.opcode),
+            gas: step.gas,
+            gas_cost: self.gas_inspector.last_gas_cost(),
+            depth: context.journaled_state.depth(),
+            error: interp
+                .instruction_result
+                .is_error()
+                .then(|| geth_error(interp.instruction_result)),
+            stack: step.stack,
+            memory: step.memory,
+            storage,
+            return_data: step.return_data.as_deref(),
+            refund: step.refund,
+        };
+        if self.steps > 1 {
+            let _ = self.output.write_all(b",");
+        }
+        let _ = serde_json::to_writer(&mut self.output, &log);
+    }
+
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        if context.journaled_state.depth() == 0 {
+            self.start();
+        }
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        let outcome = self.gas_inspector.call_end(context, inputs, outcome);
+        if context.journaled_state.depth() == 0 {
+            self.finish(context, &outcome.result);
+        }
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        _inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        if context.journaled_state.depth() == 0 {
+            self.start();
+        }
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        let outcome = self.gas_inspector.create_end(context, inputs, outcome);
+        if context.journaled_state.depth() == 0 {
+            self.finish(context, &outcome.result);
+        }
+        outcome
+    }
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CacheDB, EmptyDB},
+        inspector_handle_register,
+        primitives::{AccountInfo, Bytecode, Bytes, TxKind},
+        test_utils::{test_evm_builder, TEST_CONTRACT},
+    };
+    use serde_json::{json, Value};
+
+    fn trace(config: StructLoggerConfig, code: &'static [u8]) -> Value {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(code))),
+        );
+        let mut evm = test_evm_builder(db, TxKind::Call(TEST_CONTRACT))
+            .with_external_context(StructLogger::new(config, Vec::new()))
+            .append_handler_register(inspector_handle_register)
+            .build();
+        evm.transact().unwrap();
+        let output = evm.into_context().external.into_writer();
+        assert_eq!(output.last(), Some(&b'\n'));
+        serde_json::from_slice(&output).unwrap()
+    }
+
+    #[test]
+    fn test_struct_logger() {
+        // SSTORE(1, 2), SLOAD(1), MSTORE(0, loaded), STOP
+        let code = &[
+            0x60, 0x02, 0x60, 0x01, 0x55, 0x60, 0x01, 0x54, 0x60, 0x00, 0x52, 0x00,
+        ];
+        let config = StructLoggerConfig {
+            enable_memory: true,
+            ..Default::default()
+        };
+        let trace = trace(config, code);
+        assert_eq!(trace["gas"], 21_000 + 3 * 4 + 22_100 + 100 + 3 + 3);
+        assert_eq!(trace["failed"], false);
+        assert_eq!(trace["returnValue"], "");
+
+        let logs = trace["structLogs"].as_array().unwrap();
+        assert_eq!(logs.len(), 8);
+        let slot = format!("{:064x}", 1);
+        let value = format!("{:064x}", 2);
+        assert_eq!(
+            logs[2],
+            json!({
+                "pc": 4,
+                "op": "SSTORE",
+                "gas": 100_000 - 21_000 - 6,
+                "gasCost": 22_100,
+                "depth": 1,
+                "stack": ["0x2", "0x1"],
+                "memory": [],
+                "storage": { &slot: &value },
+            })
+        );
+        assert_eq!(logs[4]["op"], "SLOAD");
+        assert_eq!(logs[4]["storage"], json!({ &slot: &value }));
+        assert_eq!(logs[7]["memory"], json!([value]));
+        assert!(logs[7].get("storage").is_none());
+    }
+
+    #[test]
+    fn test_struct_logger_config() {
+        // PUSH1 1, PUSH1 2, INVALID
+        let config = StructLoggerConfig {
+            disable_stack: true,
+            enable_return_data: true,
+            limit: 2,
+            ..Default::default()
+        };
+        let result = trace(config, &[0x60, 0x01, 0x60, 0x02, 0xfe]);
+        assert_eq!(result["failed"], true);
+        assert_eq!(result["gas"], 100_000);
+        let logs = result["structLogs"].as_array().unwrap();
+        assert_eq!(logs.len(), 2);
+        assert!(logs[0].get("stack").is_none());
+        assert_eq!(logs[0]["returnData"], "0x");
+
+        let trace = trace(
+            StructLoggerConfig::default(),
+            &[0x60, 0x01, 0x60, 0x02, 0xfe],
+        );
+        let logs = trace["structLogs"].as_array().unwrap();
+        assert_eq!(logs[2]["error"], "invalid opcode");
+    }
+}
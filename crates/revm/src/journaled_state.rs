@@ -1,7 +1,7 @@
 use crate::{
     interpreter::{InstructionResult, LoadAccountResult, SStoreResult, SelfDestructResult},
     primitives::{
-        db::Database, hash_map::Entry, Account, Address, Bytecode, EVMError, EvmState,
+        db::Database, hash_map::Entry, Account, AccountInfo, Address, Bytecode, EVMError, EvmState,
         EvmStorageSlot, HashMap, HashSet, Log, SpecId, SpecId::*, TransientStorage, B256,
         KECCAK_EMPTY, PRECOMPILE3, U256,
     },
@@ -35,6 +35,21 @@ pub struct JournaledState {
     /// Note that this not include newly loaded accounts, account and storage
     /// is considered warm if it is found in the `State`.
     pub warm_preloaded_addresses: HashSet<Address>,
+    /// Poseidon code hashes of the code set by [JournaledState::set_code_with_hash].
+    ///
+    /// Unlike the state this is kept between transactions, see [Self::clear].
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    pub poseidon_code_hashes: crate::primitives::PoseidonCodeHashCache,
+    /// Poseidon code hashes before each [JournalEntry::CodeChange] in the journal, in the same
+    /// order, so a revert restores them without hashing the code.
+    #[cfg(feature = "scroll-poseidon-codehash")]
+    pub poseidon_code_hash_reverts: Vec<B256>,
+    /// Check consistency of accounts loaded from the database.
+    ///
+    /// Copied from [crate::primitives::CfgEnv::check_account_consistency] by the handler before
+    /// the transaction is validated, and reset by [Self::clear].
+    #[cfg(feature = "scroll")]
+    pub check_account_consistency: bool,
 }
 
 impl JournaledState {
@@ -58,6 +73,12 @@ impl JournaledState {
             depth: 0,
             spec,
             warm_preloaded_addresses,
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            poseidon_code_hashes: Default::default(),
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            poseidon_code_hash_reverts: Vec::new(),
+            #[cfg(feature = "scroll")]
+            check_account_consistency: false,
         }
     }
 
@@ -92,10 +113,16 @@ impl JournaledState {
         }
     }
 
-    /// Clears the JournaledState. Preserving only the spec.
+    /// Clears the JournaledState. Preserving only the spec and the poseidon code hash cache.
     pub fn clear(&mut self) {
         let spec = self.spec;
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        let poseidon_code_hashes = mem::take(&mut self.poseidon_code_hashes);
         *self = Self::new(spec, HashSet::new());
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        {
+            self.poseidon_code_hashes = poseidon_code_hashes;
+        }
     }
 
     /// Does cleanup and returns modified state.
@@ -112,10 +139,18 @@ impl JournaledState {
             // kept, see [Self::new]
             spec: _,
             warm_preloaded_addresses: _,
+            #[cfg(feature = "scroll-poseidon-codehash")]
+                poseidon_code_hashes: _,
+            #[cfg(feature = "scroll-poseidon-codehash")]
+            poseidon_code_hash_reverts,
+            #[cfg(feature = "scroll")]
+                check_account_consistency: _,
         } = self;
 
         *transient_storage = TransientStorage::default();
         *journal = vec![vec![]];
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        poseidon_code_hash_reverts.clear();
         *depth = 0;
         let state = mem::take(state);
         let logs = mem::take(logs);
@@ -155,6 +190,9 @@ impl JournaledState {
             .last_mut()
             .unwrap()
             .push(JournalEntry::CodeChange { address });
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        self.poseidon_code_hash_reverts
+            .push(account.info.poseidon_code_hash);
 
         account.info.code_hash = hash;
         #[cfg(feature = "scroll")]
@@ -162,7 +200,8 @@ impl JournaledState {
             account.info.code_size = code.len();
             #[cfg(feature = "scroll-poseidon-codehash")]
             {
-                account.info.poseidon_code_hash = code.poseidon_hash_slow();
+                account.info.poseidon_code_hash =
+                    self.poseidon_code_hashes.get_or_compute(hash, &code);
             }
         }
         account.info.code = Some(code);
@@ -324,6 +363,7 @@ impl JournaledState {
         transient_storage: &mut TransientStorage,
         journal_entries: Vec<JournalEntry>,
         is_spurious_dragon_enabled: bool,
+        #[cfg(feature = "scroll-poseidon-codehash")] poseidon_code_hash_reverts: &mut Vec<B256>,
     ) {
         for entry in journal_entries.into_iter().rev() {
             match entry {
@@ -422,9 +462,13 @@ impl JournaledState {
                     #[cfg(feature = "scroll")]
                     {
                         acc.info.code_size = 0;
+                        // poseidon hash of the reverted code stays in `poseidon_code_hashes`,
+                        // so setting the same code again does not recompute it.
                         #[cfg(feature = "scroll-poseidon-codehash")]
                         {
-                            acc.info.poseidon_code_hash = crate::primitives::POSEIDON_EMPTY;
+                            acc.info.poseidon_code_hash = poseidon_code_hash_reverts
+                                .pop()
+                                .unwrap_or(crate::primitives::POSEIDON_EMPTY);
                         }
                     }
                 }
@@ -456,6 +500,8 @@ impl JournaledState {
         let is_spurious_dragon_enabled = SpecId::enabled(self.spec, SPURIOUS_DRAGON);
         let state = &mut self.state;
         let transient_storage = &mut self.transient_storage;
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        let poseidon_code_hash_reverts = &mut self.poseidon_code_hash_reverts;
         self.depth -= 1;
         // iterate over last N journals sets and revert our global state
         let leng = self.journal.len();
@@ -469,6 +515,8 @@ impl JournaledState {
                     transient_storage,
                     mem::take(cs),
                     is_spurious_dragon_enabled,
+                    #[cfg(feature = "scroll-poseidon-codehash")]
+                    poseidon_code_hash_reverts,
                 )
             });
 
@@ -548,6 +596,27 @@ impl JournaledState {
         })
     }
 
+    /// Loads account info from the database.
+    ///
+    /// If `check_account_consistency` is set, inconsistent accounts are returned as
+    /// [EVMError::Account].
+    #[inline]
+    fn load_account_info<DB: Database>(
+        address: Address,
+        db: &mut DB,
+        #[cfg(feature = "scroll")] check_account_consistency: bool,
+    ) -> Result<Option<AccountInfo>, EVMError<DB::Error>> {
+        let info = db.basic(address).map_err(EVMError::Database)?;
+        #[cfg(feature = "scroll")]
+        if check_account_consistency {
+            if let Some(info) = &info {
+                info.check_consistency()
+                    .map_err(|reason| crate::primitives::InvalidAccount { address, reason })?;
+            }
+        }
+        Ok(info)
+    }
+
     /// Initial load of account. This load will not be tracked inside journal
     #[inline]
     pub fn initial_account_load<DB: Database>(
@@ -556,14 +625,20 @@ impl JournaledState {
         storage_keys: impl IntoIterator<Item = U256>,
         db: &mut DB,
     ) -> Result<&mut Account, EVMError<DB::Error>> {
+        #[cfg(feature = "scroll")]
+        let check_account_consistency = self.check_account_consistency;
         // load or get account.
         let account = match self.state.entry(address) {
             Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(vac) => vac.insert(
-                db.basic(address)
-                    .map_err(EVMError::Database)?
-                    .map(|i| i.into())
-                    .unwrap_or(Account::new_not_existing()),
+                Self::load_account_info(
+                    address,
+                    db,
+                    #[cfg(feature = "scroll")]
+                    check_account_consistency,
+                )?
+                .map(|i| i.into())
+                .unwrap_or(Account::new_not_existing()),
             ),
         };
         // preload storages.
@@ -585,6 +660,8 @@ impl JournaledState {
         address: Address,
         db: &mut DB,
     ) -> Result<(&mut Account, bool), EVMError<DB::Error>> {
+        #[cfg(feature = "scroll")]
+        let check_account_consistency = self.check_account_consistency;
         let (value, is_cold) = match self.state.entry(address) {
             Entry::Occupied(entry) => {
                 let account = entry.into_mut();
@@ -592,12 +669,16 @@ impl JournaledState {
                 (account, is_cold)
             }
             Entry::Vacant(vac) => {
-                let account =
-                    if let Some(account) = db.basic(address).map_err(EVMError::Database)? {
-                        account.into()
-                    } else {
-                        Account::new_not_existing()
-                    };
+                let account = if let Some(account) = Self::load_account_info(
+                    address,
+                    db,
+                    #[cfg(feature = "scroll")]
+                    check_account_consistency,
+                )? {
+                    account.into()
+                } else {
+                    Account::new_not_existing()
+                };
 
                 // precompiles are warm loaded so we need to take that into account
                 let is_cold = !self.warm_preloaded_addresses.contains(&address);
@@ -648,7 +729,13 @@ impl JournaledState {
         address: Address,
         db: &mut DB,
     ) -> Result<(&mut Account, bool), EVMError<DB::Error>> {
+        #[cfg(not(feature = "scroll-poseidon-codehash"))]
         let (acc, is_cold) = self.load_account(address, db)?;
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        let (acc, is_cold) = {
+            let is_cold = self.load_account(address, db)?.1;
+            (self.state.get_mut(&address).unwrap(), is_cold)
+        };
         if acc.info.code.is_none() {
             if acc.info.is_empty_code_hash() {
                 let empty = Bytecode::default();
@@ -660,6 +747,9 @@ impl JournaledState {
                 acc.info.code = Some(code);
             }
         }
+        #[cfg(feature = "scroll-poseidon-codehash")]
+        acc.info
+            .fill_poseidon_code_hash(&mut self.poseidon_code_hashes);
         Ok((acc, is_cold))
     }
 
@@ -892,3 +982,31 @@ pub struct JournalCheckpoint {
     log_i: usize,
     journal_i: usize,
 }
+
+#[cfg(all(test, feature = "scroll-poseidon-codehash"))]
+mod tests {
+    use super::*;
+    use crate::{db::EmptyDB, primitives::Bytes};
+
+    #[test]
+    fn code_change_revert_restores_poseidon_code_hash() {
+        let address = Address::repeat_byte(0x10);
+        let code = Bytecode::new_raw(Bytes::from_static(&[0x60, 0x01, 0x00]));
+        let mut db = EmptyDB::default();
+        let mut journaled_state = JournaledState::new(SpecId::LATEST, HashSet::new());
+        journaled_state.load_account(address, &mut db).unwrap();
+        let before = journaled_state.account(address).info.poseidon_code_hash;
+
+        let checkpoint = journaled_state.checkpoint();
+        journaled_state.set_code(address, code.clone());
+        assert_eq!(
+            journaled_state.account(address).info.poseidon_code_hash,
+            code.poseidon_hash_slow()
+        );
+
+        journaled_state.checkpoint_revert(checkpoint);
+        let info = &journaled_state.account(address).info;
+        assert_eq!(info.poseidon_code_hash, before);
+        assert_eq!(info.code_hash, KECCAK_EMPTY);
+    }
+}
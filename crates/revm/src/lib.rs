@@ -8,6 +8,8 @@ extern crate alloc as std;
 
 // Define modules.
 
+mod access_list;
+pub mod block_hash;
 mod builder;
 mod context;
 
@@ -15,6 +17,7 @@ mod context;
 pub mod test_utils;
 
 pub mod db;
+mod estimate_gas;
 mod evm;
 mod frame;
 pub mod handler;
@@ -24,9 +27,17 @@ mod journaled_state;
 pub mod optimism;
 #[cfg(feature = "scroll")]
 pub mod scroll;
+mod simulate;
 
 // Export items.
 
+pub use access_list::AccessListResult;
+#[cfg(feature = "scroll")]
+pub use block_hash::ScrollBlockHashes;
+pub use block_hash::{
+    BlockHashProvider, BlockHashProviderBox, BlockHashRing, Eip2935BlockHashes,
+    SpecBlockHashProvider,
+};
 pub use builder::EvmBuilder;
 pub use context::{
     Context, ContextPrecompile, ContextPrecompiles, ContextStatefulPrecompile,
@@ -37,11 +48,13 @@ pub use db::{
     CacheState, DBBox, State, StateBuilder, StateDBBox, TransitionAccount, TransitionState,
 };
 pub use db::{Database, DatabaseCommit, DatabaseRef, InMemoryDB};
+pub use estimate_gas::GasEstimation;
 pub use evm::{Evm, CALL_STACK_LIMIT};
 pub use frame::{CallFrame, CreateFrame, Frame, FrameData, FrameOrResult, FrameResult};
 pub use handler::Handler;
 pub use inspector::{inspector_handle_register, inspectors, GetInspector, Inspector};
 pub use journaled_state::{JournalCheckpoint, JournalEntry, JournaledState};
+pub use simulate::{SimulatedBlock, SimulatedBlockResult, SIMULATED_BLOCK_TIME};
 // export Optimism types, helpers, and constants
 #[cfg(feature = "optimism")]
 pub use optimism::{L1BlockInfo, BASE_FEE_RECIPIENT, L1_BLOCK_CONTRACT, L1_FEE_RECIPIENT};
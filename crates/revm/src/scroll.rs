@@ -1,7 +1,11 @@
 mod handler_register;
+mod l1_message_queue;
 mod l1block;
 
 pub use crate::scroll::handler_register::{
-    deduct_caller, load_accounts, reward_beneficiary, scroll_handle_register,
+    deduct_caller, load_accounts, output, reward_beneficiary, scroll_handle_register,
+};
+pub use crate::scroll::l1_message_queue::{
+    compute_l1_message_queue_hash, L1MessageQueueError, L1MessageQueueState,
 };
 pub use crate::scroll::l1block::{L1BlockInfo, L1_GAS_PRICE_ORACLE_ADDRESS};
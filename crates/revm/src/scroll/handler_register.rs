@@ -6,9 +6,10 @@ use crate::{
     handler::register::EvmHandler,
     interpreter::Gas,
     primitives::{
-        db::Database, spec_to_generic, EVMError, InvalidTransaction, Spec, SpecId, TransactTo, U256,
+        db::Database, spec_to_generic, EVMError, ExecutionResult, HaltReason, InvalidTransaction,
+        ResultAndState, Spec, SpecId, TransactTo, U256,
     },
-    Context,
+    Context, FrameResult,
 };
 #[cfg(not(feature = "std"))]
 use std::string::ToString;
@@ -22,6 +23,8 @@ pub fn scroll_handle_register<DB: Database, EXT>(handler: &mut EvmHandler<'_, EX
         handler.pre_execution.deduct_caller = Arc::new(deduct_caller::<SPEC, EXT, DB>);
         // basefee is sent to coinbase
         handler.post_execution.reward_beneficiary = Arc::new(reward_beneficiary::<SPEC, EXT, DB>);
+        // transactions using unsupported precompile inputs must be skipped.
+        handler.post_execution.output = Arc::new(output::<SPEC, EXT, DB>);
     });
 }
 
@@ -34,6 +37,7 @@ pub fn load_accounts<SPEC: Spec, EXT, DB: Database>(
         crate::scroll::L1BlockInfo::try_fetch(&mut context.evm.inner.db, SPEC::SPEC_ID)
             .map_err(EVMError::Database)?;
     context.evm.inner.l1_block_info = Some(l1_block_info);
+    context.evm.inner.precompile_not_implemented = false;
 
     mainnet::load_accounts::<SPEC, EXT, DB>(context)
 }
@@ -133,3 +137,102 @@ pub fn reward_beneficiary<SPEC: Spec, EXT, DB: Database>(
 
     Ok(())
 }
+
+/// Main return handle, returns the output of the transaction.
+///
+/// If any precompile call was not supported by the Scroll circuits, the result is replaced with
+/// [HaltReason::PrecompileNotImplemented] and the state is emptied, so committing the
+/// transaction leaves the database unchanged, see [ExecutionResult::must_skip].
+#[inline]
+pub fn output<SPEC: Spec, EXT, DB: Database>(
+    context: &mut Context<EXT, DB>,
+    frame_result: FrameResult,
+) -> Result<ResultAndState, EVMError<DB::Error>> {
+    let mut result = mainnet::output::<EXT, DB>(context, frame_result)?;
+
+    if context.evm.inner.precompile_not_implemented {
+        result.result = ExecutionResult::Halt {
+            reason: HaltReason::PrecompileNotImplemented,
+            gas_used: result.result.gas_used(),
+        };
+        // the transaction is not included in the block, so none of its changes apply, including
+        // the fee and the nonce.
+        result.state = Default::default();
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::InMemoryDB,
+        primitives::{address, bytes, AccountInfo, Bytecode},
+        Evm,
+    };
+
+    #[test]
+    fn test_precompile_not_implemented_must_skip() {
+        let contract = address!("dead10000000000000000000000000000001dead");
+        // STATICCALL to SHA256 precompile, then STOP.
+        let code = Bytecode::new_raw(bytes!("600060006000600060025afa00"));
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(contract, AccountInfo::from_bytecode(code));
+
+        let mut evm = Evm::builder()
+            .with_db(db)
+            .scroll()
+            .with_spec_id(SpecId::PRE_BERNOULLI)
+            .modify_tx_env(|tx| {
+                tx.transact_to = TransactTo::Call(contract);
+                tx.gas_limit = 100_000;
+                tx.scroll.is_l1_msg = true;
+            })
+            .build();
+        let result = evm.transact().unwrap().result;
+        assert!(result.must_skip());
+
+        // SHA256 is supported after Bernoulli.
+        evm = evm.modify().with_spec_id(SpecId::BERNOULLI).build();
+        let result = evm.transact().unwrap().result;
+        assert!(result.is_success());
+        assert!(!result.must_skip());
+    }
+
+    #[test]
+    fn test_precompile_not_implemented_commit() {
+        let caller = address!("1000000000000000000000000000000000000000");
+        let contract = address!("2000000000000000000000000000000000000000");
+        // SSTORE(0, 1), STATICCALL to SHA256 precompile, then STOP.
+        let code = Bytecode::new_raw(bytes!("6001600055600060006000600060025afa00"));
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(contract, AccountInfo::from_bytecode(code));
+        db.insert_account_info(
+            caller,
+            AccountInfo::from_balance(U256::from(10).pow(U256::from(18))),
+        );
+
+        let mut evm = Evm::builder()
+            .with_db(db)
+            .scroll()
+            .with_spec_id(SpecId::PRE_BERNOULLI)
+            .modify_tx_env(|tx| {
+                tx.caller = caller;
+                tx.transact_to = TransactTo::Call(contract);
+                tx.gas_limit = 100_000;
+                tx.gas_price = U256::from(1);
+                tx.scroll.rlp_bytes = Some(Default::default());
+            })
+            .build();
+        let result = evm.transact_commit().unwrap();
+        assert!(result.must_skip());
+
+        // nothing is committed, neither the fee and nonce nor the storage change.
+        let db = &evm.context.evm.db;
+        let caller_info = &db.accounts[&caller].info;
+        assert_eq!(caller_info.nonce, 0);
+        assert_eq!(caller_info.balance, U256::from(10).pow(U256::from(18)));
+        let slot = db.accounts[&contract].storage.get(&U256::ZERO);
+        assert_eq!(slot.copied().unwrap_or_default(), U256::ZERO);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::primitives::{keccak256, TxEnv, B256};
+use core::fmt;
+
+/// Computes the next rolling L1 message queue hash.
+///
+/// The rolling hash is `keccak256(prev_hash || message_hash)` with the last 32 bits cleared,
+/// matching the `L1MessageQueueV2` contract which stores the enqueue timestamp in those bits.
+#[inline]
+pub fn compute_l1_message_queue_hash(prev_hash: B256, message_hash: B256) -> B256 {
+    let mut input = [0u8; 64];
+    input[..32].copy_from_slice(prev_hash.as_slice());
+    input[32..].copy_from_slice(message_hash.as_slice());
+    let mut hash = keccak256(input);
+    hash[28..].fill(0);
+    hash
+}
+
+/// State of the L1 message queue at a block boundary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct L1MessageQueueState {
+    /// Queue index of the next L1 message expected to be included.
+    pub next_queue_index: u64,
+    /// Rolling hash over all L1 messages included so far.
+    pub rolling_hash: B256,
+}
+
+impl L1MessageQueueState {
+    /// Creates a new queue state.
+    pub const fn new(next_queue_index: u64, rolling_hash: B256) -> Self {
+        Self {
+            next_queue_index,
+            rolling_hash,
+        }
+    }
+
+    /// Folds a single L1 message into the queue state.
+    ///
+    /// The message must have the next expected queue index, otherwise the state is left unchanged.
+    pub fn apply_message(
+        &mut self,
+        queue_index: u64,
+        message_hash: B256,
+    ) -> Result<(), L1MessageQueueError> {
+        if queue_index != self.next_queue_index {
+            return Err(L1MessageQueueError::QueueIndexMismatch {
+                expected: self.next_queue_index,
+                got: queue_index,
+            });
+        }
+        self.rolling_hash = compute_l1_message_queue_hash(self.rolling_hash, message_hash);
+        self.next_queue_index += 1;
+        Ok(())
+    }
+
+    /// Folds the transaction into the queue state if it is an L1 message.
+    ///
+    /// Returns `true` if the transaction was an L1 message.
+    pub fn apply_tx(&mut self, tx: &TxEnv) -> Result<bool, L1MessageQueueError> {
+        if !tx.scroll.is_l1_msg {
+            return Ok(false);
+        }
+        let queue_index = tx
+            .scroll
+            .queue_index
+            .ok_or(L1MessageQueueError::MissingQueueIndex)?;
+        let message_hash = tx
+            .scroll
+            .message_hash
+            .ok_or(L1MessageQueueError::MissingMessageHash { queue_index })?;
+        self.apply_message(queue_index, message_hash)?;
+        Ok(true)
+    }
+
+    /// Folds all L1 messages executed in a block into the queue state.
+    ///
+    /// Transactions that are not L1 messages are skipped. On error the state is left unchanged.
+    pub fn apply_block<'a, I>(&mut self, txs: I) -> Result<(), L1MessageQueueError>
+    where
+        I: IntoIterator<Item = &'a TxEnv>,
+    {
+        let mut state = *self;
+        for tx in txs {
+            state.apply_tx(tx)?;
+        }
+        *self = state;
+        Ok(())
+    }
+}
+
+/// Error returned when folding L1 messages into the [`L1MessageQueueState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum L1MessageQueueError {
+    /// L1 message transaction has no queue index set.
+    MissingQueueIndex,
+    /// L1 message transaction has no message hash set.
+    MissingMessageHash { queue_index: u64 },
+    /// L1 message queue index does not follow the previous one.
+    QueueIndexMismatch { expected: u64, got: u64 },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for L1MessageQueueError {}
+
+impl fmt::Display for L1MessageQueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingQueueIndex => write!(f, "L1 message is missing its queue index"),
+            Self::MissingMessageHash { queue_index } => {
+                write!(f, "L1 message {queue_index} is missing its message hash")
+            }
+            Self::QueueIndexMismatch { expected, got } => {
+                write!(
+                    f,
+                    "L1 message queue index {got} does not match expected {expected}"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::b256;
+
+    fn l1_msg(queue_index: u64, message_hash: B256) -> TxEnv {
+        let mut tx = TxEnv::default();
+        tx.scroll.is_l1_msg = true;
+        tx.scroll.queue_index = Some(queue_index);
+        tx.scroll.message_hash = Some(message_hash);
+        tx
+    }
+
+    #[test]
+    fn test_rolling_hash_clears_low_bits() {
+        let hash = compute_l1_message_queue_hash(B256::ZERO, B256::repeat_byte(0x11));
+        assert_eq!(&hash[28..], &[0u8; 4]);
+
+        let mut input = [0u8; 64];
+        input[32..].fill(0x11);
+        assert_eq!(hash[..28], keccak256(input)[..28]);
+    }
+
+    #[test]
+    fn test_apply_block() {
+        let hash1 = b256!("0101010101010101010101010101010101010101010101010101010101010101");
+        let hash2 = b256!("0202020202020202020202020202020202020202020202020202020202020202");
+        let txs = [l1_msg(5, hash1), TxEnv::default(), l1_msg(6, hash2)];
+
+        let mut state = L1MessageQueueState::new(5, B256::ZERO);
+        state.apply_block(&txs).unwrap();
+
+        let expected =
+            compute_l1_message_queue_hash(compute_l1_message_queue_hash(B256::ZERO, hash1), hash2);
+        assert_eq!(state, L1MessageQueueState::new(7, expected));
+    }
+
+    #[test]
+    fn test_apply_block_index_gap() {
+        let txs = [l1_msg(5, B256::ZERO), l1_msg(7, B256::ZERO)];
+
+        let mut state = L1MessageQueueState::new(5, B256::ZERO);
+        assert_eq!(
+            state.apply_block(&txs),
+            Err(L1MessageQueueError::QueueIndexMismatch {
+                expected: 6,
+                got: 7
+            })
+        );
+        // state is not modified on error.
+        assert_eq!(state, L1MessageQueueState::new(5, B256::ZERO));
+    }
+
+    #[test]
+    fn test_apply_tx_missing_fields() {
+        let mut state = L1MessageQueueState::default();
+        let mut tx = l1_msg(0, B256::ZERO);
+        tx.scroll.message_hash = None;
+        assert_eq!(
+            state.apply_tx(&tx),
+            Err(L1MessageQueueError::MissingMessageHash { queue_index: 0 })
+        );
+        tx.scroll.queue_index = None;
+        assert_eq!(
+            state.apply_tx(&tx),
+            Err(L1MessageQueueError::MissingQueueIndex)
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Multi-block simulation, same as the `eth_simulateV1` RPC method.
+
+use crate::{
+    db::{states::bundle_state::BundleRetention, BlockOverrides, State},
+    primitives::{BlockEnv, CfgEnv, EVMError, ExecutionResult, TxEnv, U256},
+    Database, Evm,
+};
+use std::vec::Vec;
+
+/// Number of seconds between simulated blocks if the timestamp is not overridden.
+pub const SIMULATED_BLOCK_TIME: u64 = 12;
+
+/// Block of a simulation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimulatedBlock {
+    /// Overrides of the block environment.
+    pub block_overrides: BlockOverrides,
+    /// Transactions of the block, executed in order.
+    pub calls: Vec<TxEnv>,
+}
+
+/// Result of a [SimulatedBlock].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulatedBlockResult {
+    /// Block environment the transactions were executed with.
+    pub block: BlockEnv,
+    /// Gas used by all transactions of the block.
+    pub gas_used: u64,
+    /// Results of the transactions, containing their logs and gas used.
+    pub calls: Vec<ExecutionResult>,
+}
+
+impl<EXT, DB: Database> Evm<'_, EXT, State<DB>> {
+    /// Executes a sequence of simulated blocks on top of the current block.
+    ///
+    /// Each block is a child of the previous one: its number is incremented and its timestamp
+    /// is increased by [SIMULATED_BLOCK_TIME] before the [BlockOverrides] are applied. Transactions
+    /// are committed to the [State] and the transitions are merged into the bundle at the end of
+    /// each block, so the bundle contains one revert per block if bundle updates are enabled.
+    ///
+    /// If `validation` is `false` the nonce check is skipped, as well as the balance and base fee
+    /// checks if the `optional_balance_check` and `optional_no_base_fee` features are enabled.
+    ///
+    /// The environment of the [Evm] is left unchanged.
+    pub fn simulate(
+        &mut self,
+        blocks: Vec<SimulatedBlock>,
+        validation: bool,
+    ) -> Result<Vec<SimulatedBlockResult>, EVMError<DB::Error>> {
+        let env = self.context.evm.env.clone();
+        if !validation {
+            disable_validation(self.cfg_mut());
+        }
+        let results = blocks
+            .into_iter()
+            .map(|block| self.simulate_block(block, validation))
+            .collect();
+        self.context.evm.env = env;
+        results
+    }
+
+    fn simulate_block(
+        &mut self,
+        block: SimulatedBlock,
+        validation: bool,
+    ) -> Result<SimulatedBlockResult, EVMError<DB::Error>> {
+        let block_env = self.block_mut();
+        block_env.number += U256::from(1);
+        block_env.timestamp += U256::from(SIMULATED_BLOCK_TIME);
+        block.block_overrides.apply(block_env);
+
+        let mut gas_used = 0;
+        let mut calls = Vec::with_capacity(block.calls.len());
+        for mut tx in block.calls {
+            if !validation {
+                tx.nonce = None;
+            }
+            *self.tx_mut() = tx;
+            let result = self.transact_commit()?;
+            gas_used += result.gas_used();
+            calls.push(result);
+        }
+        self.db_mut().merge_transitions(BundleRetention::Reverts);
+
+        Ok(SimulatedBlockResult {
+            block: self.block().clone(),
+            gas_used,
+            calls,
+        })
+    }
+}
+
+/// Disables the balance and base fee checks that are optional.
+fn disable_validation(_cfg: &mut CfgEnv) {
+    #[cfg(feature = "optional_balance_check")]
+    {
+        _cfg.disable_balance_check = true;
+    }
+    #[cfg(feature = "optional_no_base_fee")]
+    {
+        _cfg.disable_base_fee = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitives::{AccountInfo, Address, Bytecode, Bytes, TxKind},
+        test_utils::{TEST_CALLER, TEST_CONTRACT},
+    };
+
+    #[test]
+    fn test_simulate() {
+        let mut state = State::builder().with_bundle_update().build();
+        state.insert_account(
+            TEST_CALLER,
+            AccountInfo::from_balance(U256::from(1_000_000)),
+        );
+        // SSTORE(0, NUMBER)
+        state.insert_account(
+            TEST_CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                0x43, 0x60, 0x00, 0x55, 0x00,
+            ]))),
+        );
+
+        let mut evm = Evm::builder().with_db(state).build();
+        let call = |nonce| TxEnv {
+            caller: TEST_CALLER,
+            transact_to: TxKind::Call(TEST_CONTRACT),
+            gas_limit: 100_000,
+            nonce: Some(nonce),
+            ..Default::default()
+        };
+        let blocks = vec![
+            SimulatedBlock {
+                block_overrides: BlockOverrides::default(),
+                calls: vec![call(0)],
+            },
+            SimulatedBlock {
+                block_overrides: BlockOverrides {
+                    number: Some(U256::from(100)),
+                    ..Default::default()
+                },
+                calls: vec![call(1), call(2)],
+            },
+        ];
+        let results = evm.simulate(blocks, true).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].block.number, U256::from(1));
+        assert_eq!(results[0].block.timestamp, U256::from(13));
+        assert_eq!(results[1].block.number, U256::from(100));
+        assert_eq!(results[1].block.timestamp, U256::from(25));
+        assert_eq!(results[1].calls.len(), 2);
+        assert!(results
+            .iter()
+            .flat_map(|block| &block.calls)
+            .all(ExecutionResult::is_success));
+        assert_eq!(
+            results[1].gas_used,
+            results[1]
+                .calls
+                .iter()
+                .map(ExecutionResult::gas_used)
+                .sum::<u64>()
+        );
+
+        // environment is restored.
+        assert_eq!(evm.block().number, U256::ZERO);
+
+        let bundle = evm.db_mut().take_bundle();
+        assert_eq!(bundle.reverts.len(), 2);
+        assert_eq!(
+            bundle
+                .account(&TEST_CONTRACT)
+                .unwrap()
+                .storage_slot(U256::ZERO),
+            Some(U256::from(100))
+        );
+        assert_eq!(
+            bundle
+                .account(&TEST_CALLER)
+                .unwrap()
+                .info
+                .as_ref()
+                .unwrap()
+                .nonce,
+            3
+        );
+    }
+
+    #[test]
+    fn test_simulate_without_validation() {
+        let mut evm = Evm::builder()
+            .with_db(State::builder().with_bundle_update().build())
+            .build();
+        let blocks = vec![SimulatedBlock {
+            block_overrides: BlockOverrides::default(),
+            calls: vec![TxEnv {
+                caller: TEST_CALLER,
+                transact_to: TxKind::Call(Address::ZERO),
+                nonce: Some(5),
+                ..Default::default()
+            }],
+        }];
+
+        assert!(evm.simulate(blocks.clone(), true).is_err());
+        let results = evm.simulate(blocks, false).unwrap();
+        assert!(results[0].calls[0].is_success());
+    }
+}
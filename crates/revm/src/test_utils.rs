@@ -1,2 +1,42 @@
 #[doc(hidden)]
 pub use crate::context::evm_context::test_utils::*;
+
+#[cfg(test)]
+use crate::{
+    builder::SetGenericStage,
+    db::{Database, DatabaseCommit},
+    primitives::{address, Address, EVMError, ExecutionResult, TxKind},
+    Evm, EvmBuilder,
+};
+
+/// Caller of the test transactions.
+#[cfg(test)]
+pub(crate) const TEST_CALLER: Address = address!("1000000000000000000000000000000000000000");
+
+/// Contract called by the test transactions.
+#[cfg(test)]
+pub(crate) const TEST_CONTRACT: Address = address!("2000000000000000000000000000000000000000");
+
+/// Returns an [Evm] builder with a transaction from [TEST_CALLER] to `transact_to` with a gas
+/// limit of 100_000.
+#[cfg(test)]
+pub(crate) fn test_evm_builder<'a, DB: Database>(
+    db: DB,
+    transact_to: TxKind,
+) -> EvmBuilder<'a, SetGenericStage, (), DB> {
+    Evm::builder().with_db(db).modify_tx_env(|tx| {
+        tx.caller = TEST_CALLER;
+        tx.transact_to = transact_to;
+        tx.gas_limit = 100_000;
+    })
+}
+
+/// Calls [TEST_CONTRACT] from [TEST_CALLER] and commits the result to `db`.
+#[cfg(test)]
+pub(crate) fn test_call_commit<DB: Database + DatabaseCommit>(
+    db: DB,
+) -> Result<ExecutionResult, EVMError<DB::Error>> {
+    test_evm_builder(db, TxKind::Call(TEST_CONTRACT))
+        .build()
+        .transact_commit()
+}